@@ -0,0 +1,142 @@
+use itertools::Itertools;
+use reqwest::StatusCode;
+use serde::{Serialize, Serializer};
+
+use crate::{full_time, Edited};
+
+/// The header Spond identifies each request with, to quote when reporting a problem.
+const REQUEST_ID_HEADER: &str = "x-request-id";
+
+fn serialize_status<S: Serializer>(status: &StatusCode, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_u16(status.as_u16())
+}
+
+/// Why Spond rejected a request, as given in its error response.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SpondApiError {
+    #[serde(serialize_with = "serialize_status")]
+    pub status: StatusCode,
+    /// The method and path of the request, e.g. `POST /sponds`.
+    pub endpoint: String,
+    pub request_id: Option<String>,
+    /// The message from Spond's JSON error body, or the whole body if it has none.
+    pub message: String,
+}
+
+impl SpondApiError {
+    /// Reads the error from a response with an error status to `endpoint`.
+    pub(crate) async fn from_response(endpoint: String, response: reqwest::Response) -> Self {
+        let status = response.status();
+        let request_id = response
+            .headers()
+            .get(REQUEST_ID_HEADER)
+            .and_then(|id| id.to_str().ok())
+            .map(|id| id.to_owned());
+        let body = response.text().await.unwrap_or_default();
+        let message = serde_json::from_str::<serde_json::Value>(&body)
+            .ok()
+            .and_then(|json| {
+                ["message", "error", "errorMessage"]
+                    .iter()
+                    .find_map(|field| json.get(field)?.as_str().map(|m| m.to_owned()))
+            })
+            .unwrap_or_else(|| body.trim().to_owned());
+        Self {
+            status,
+            endpoint,
+            request_id,
+            message,
+        }
+    }
+}
+
+impl std::fmt::Display for SpondApiError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} responded {}: {}",
+            self.endpoint, self.status, self.message
+        )?;
+        if let Some(request_id) = &self.request_id {
+            write!(f, " (request id {request_id})")?;
+        }
+        Ok(())
+    }
+}
+
+/// The ways syncing a team can fail, so that callers can tell e.g. an expired
+/// password from a change to Full-Time's markup.
+#[derive(Debug)]
+pub enum Error {
+    /// The config couldn't be found, read or parsed.
+    Config(Box<figment::Error>),
+    /// Full-Time's fixtures couldn't be fetched or read.
+    FullTimeFetch(full_time::Error),
+    /// Full-Time's fixtures couldn't be parsed, e.g. because its markup changed.
+    FullTimeParse(full_time::ParseError),
+    /// Spond rejected the credentials or the session.
+    SpondAuth(SpondApiError),
+    /// Spond responded with an error status.
+    SpondApi(SpondApiError),
+    /// A request to Spond couldn't be sent, or its response couldn't be read.
+    SpondRequest(reqwest::Error),
+    /// The sync state couldn't be read or written.
+    State(rusqlite::Error),
+    /// Sponds were edited since they were last synced and the edit policy is
+    /// to refuse to overwrite them.
+    Edited(Vec<Edited>),
+    /// A run, or one of its changes, can't be undone.
+    CannotUndo(String),
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Config(e) => write!(f, "invalid config: {e}"),
+            Error::FullTimeFetch(e) => write!(f, "{e}"),
+            Error::FullTimeParse(e) => write!(f, "failed to parse Full-Time fixtures: {e}"),
+            Error::SpondAuth(e) => write!(f, "Spond rejected the credentials: {e}"),
+            Error::SpondApi(e) => write!(f, "{e}"),
+            Error::SpondRequest(e) => write!(f, "failed to call Spond: {e}"),
+            Error::State(e) => write!(f, "failed to read or write the sync state: {e}"),
+            Error::Edited(edited) => write!(f, "{}", edited.iter().join("; ")),
+            Error::CannotUndo(reason) => write!(f, "{reason}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Config(e) => Some(e),
+            Error::FullTimeFetch(e) => Some(e),
+            Error::FullTimeParse(e) => Some(e),
+            Error::SpondRequest(e) => Some(e),
+            Error::State(e) => Some(e),
+            Error::SpondAuth(_) | Error::SpondApi(_) | Error::Edited(_) | Error::CannotUndo(_) => {
+                None
+            }
+        }
+    }
+}
+
+impl From<Box<figment::Error>> for Error {
+    fn from(e: Box<figment::Error>) -> Self {
+        Error::Config(e)
+    }
+}
+
+impl From<full_time::Error> for Error {
+    fn from(e: full_time::Error) -> Self {
+        match e {
+            full_time::Error::Parse(e) => Error::FullTimeParse(e),
+            e => Error::FullTimeFetch(e),
+        }
+    }
+}
+
+impl From<rusqlite::Error> for Error {
+    fn from(e: rusqlite::Error) -> Self {
+        Error::State(e)
+    }
+}
@@ -1,7 +1,9 @@
 use chrono::prelude::*;
 use chrono_tz::{Europe::London, Tz};
-use reqwest::Error;
 use scraper::{ElementRef, Html, Selector};
+use serde::{Deserialize, Serialize};
+
+use crate::{http, venue};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct SeasonId(i32);
@@ -52,46 +54,276 @@ pub struct Team {
     pub name: TeamName,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum FixtureType {
     Cup,
     League,
 }
 
-#[derive(Copy, Debug, Clone, PartialEq, Eq)]
+#[derive(Copy, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum FixtureSide {
     Home,
     Away,
 }
 
-#[derive(Copy, Debug, Clone, PartialEq, Eq)]
+/// Serialised as `{"known": "<registry name>"}` or
+/// `{"other": {"name": "...", "address": "..."}}`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum Venue {
-    Goals,
-    KingsAcademy,
-    WoodfordPark3G,
-    WoodfordParkGrass,
-    CintraPark,
-    NorthStreetTheale,
+    /// A ground from the venue registry, identified by its name.
+    Known(String),
+    /// A ground we don't know about, typically the opposition's for an away fixture.
+    Other { name: String, address: String },
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+impl std::fmt::Display for Venue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Venue::Known(name) => write!(f, "{name}"),
+            Venue::Other { name, .. } => write!(f, "{name}"),
+        }
+    }
+}
+
+/// The id Full-Time uses for a fixture in its `displayFixture.html` links.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct FixtureId(String);
+
+impl FixtureId {
+    pub fn new(s: impl Into<String>) -> Self {
+        Self(s.into())
+    }
+}
+
+impl std::ops::Deref for FixtureId {
+    type Target = str;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+/// Identifies a fixture independently of when it's scheduled, so that a
+/// rescheduled fixture can still be matched with the Spond created for it.
+///
+/// This is the Full-Time fixture id when there is one, otherwise it falls back
+/// to the competition and the opposition.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct FixtureKey(String);
+
+impl FixtureKey {
+    const ID_PREFIX: &'static str = "id:";
+
+    pub fn new(s: impl Into<String>) -> Self {
+        Self(s.into())
+    }
+
+    pub fn fixture_id(&self) -> Option<FixtureId> {
+        self.0.strip_prefix(Self::ID_PREFIX).map(FixtureId::new)
+    }
+}
+
+impl std::ops::Deref for FixtureKey {
+    type Target = str;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+/// A fixture scraped from Full-Time. Serialised with `type` as `cup` or
+/// `league`, `side` as `home` or `away` and `date_time` in RFC 3339 format.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Fixture {
+    pub id: Option<FixtureId>,
+    #[serde(rename = "type")]
     pub typ: FixtureType,
     pub side: FixtureSide,
+    #[serde(deserialize_with = "deserialize_london_date_time")]
     pub date_time: DateTime<Tz>,
     pub opposition: String,
     pub venue: Venue,
 }
 
-fn parse_fixture_type(cell: &ElementRef) -> FixtureType {
+fn deserialize_london_date_time<'de, D: serde::Deserializer<'de>>(
+    deserializer: D,
+) -> Result<DateTime<Tz>, D::Error> {
+    Ok(DateTime::<FixedOffset>::deserialize(deserializer)?.with_timezone(&London))
+}
+
+impl Fixture {
+    /// The key of the fixture with the given Full-Time id.
+    pub fn key_for_id(id: &FixtureId) -> FixtureKey {
+        FixtureKey(format!("{}{}", FixtureKey::ID_PREFIX, id.0))
+    }
+
+    /// The key of a fixture without an id, e.g. `league:reading city u9 blues`.
+    pub fn key_for_opposition(typ: &FixtureType, opposition: &str) -> FixtureKey {
+        FixtureKey(format!(
+            "{}:{}",
+            match typ {
+                FixtureType::Cup => "cup",
+                FixtureType::League => "league",
+            },
+            opposition.to_lowercase()
+        ))
+    }
+
+    pub fn key(&self) -> FixtureKey {
+        match &self.id {
+            Some(id) => Self::key_for_id(id),
+            None => self.opposition_key(),
+        }
+    }
+
+    pub fn opposition_key(&self) -> FixtureKey {
+        Self::key_for_opposition(&self.typ, &self.opposition)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseError {
+    UnknownFixtureType(String),
+    MissingDateTime,
+    InvalidDateTime(String),
+    AmbiguousLocalTime(String),
+    InvalidTeamCell(String),
+    TeamNotInFixture { home: String, away: String },
+    IncorrectCellCount(usize),
+    MultipleFixtureTables(usize),
+}
+
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseError::UnknownFixtureType(typ) => write!(f, "unknown fixture type '{typ}'"),
+            ParseError::MissingDateTime => {
+                write!(
+                    f,
+                    "expected exactly two span elements in the date time cell"
+                )
+            }
+            ParseError::InvalidDateTime(date_time) => {
+                write!(f, "invalid date time format '{date_time}'")
+            }
+            ParseError::AmbiguousLocalTime(date_time) => {
+                write!(f, "'{date_time}' is not a valid London time")
+            }
+            ParseError::InvalidTeamCell(cell) => {
+                write!(f, "expected a single anchor tag in team cell '{cell}'")
+            }
+            ParseError::TeamNotInFixture { home, away } => write!(
+                f,
+                "team wasn't listed as either home or away in '{home}' vs '{away}'"
+            ),
+            ParseError::IncorrectCellCount(count) => {
+                write!(f, "expected 8 cells in the fixture row, but found {count}")
+            }
+            ParseError::MultipleFixtureTables(count) => {
+                write!(f, "expected to find one fixture table, but found {count}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+impl Serialize for ParseError {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+/// A row of the fixture table which couldn't be parsed into a fixture.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SkippedRow {
+    /// The zero based index of the row within the fixture table.
+    pub row: usize,
+    pub text: String,
+    pub reason: ParseError,
+    /// The row's fixture id and opposition, if they could be parsed, so that
+    /// its Spond can be told apart from a removed fixture's.
+    pub id: Option<FixtureId>,
+    pub opposition: Option<String>,
+}
+
+impl std::fmt::Display for SkippedRow {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "row {} ({}): {}", self.row, self.text, self.reason)
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct UpcomingFixtures {
+    pub fixtures: Vec<Fixture>,
+    pub skipped: Vec<SkippedRow>,
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Request(reqwest::Error),
+    Io(std::io::Error),
+    Parse(ParseError),
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Request(e) => write!(f, "failed to fetch Full-Time fixtures: {e}"),
+            Error::Io(e) => write!(f, "failed to read Full-Time fixtures: {e}"),
+            Error::Parse(e) => write!(f, "failed to parse Full-Time fixtures: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Request(e) => Some(e),
+            Error::Io(e) => Some(e),
+            Error::Parse(e) => Some(e),
+        }
+    }
+}
+
+impl From<reqwest::Error> for Error {
+    fn from(e: reqwest::Error) -> Self {
+        Error::Request(e)
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(e: std::io::Error) -> Self {
+        Error::Io(e)
+    }
+}
+
+impl From<ParseError> for Error {
+    fn from(e: ParseError) -> Self {
+        Error::Parse(e)
+    }
+}
+
+fn cell_text(cell: &ElementRef) -> String {
+    cell.text()
+        .map(|s| s.trim())
+        .filter(|s| !s.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn parse_fixture_type(cell: &ElementRef) -> Result<FixtureType, ParseError> {
     match cell.inner_html().trim().to_lowercase().as_str() {
-        "l" | "o" => FixtureType::League,
-        "cup" => FixtureType::Cup,
-        x => panic!("Unknown fixture type {x}."),
+        "l" | "o" => Ok(FixtureType::League),
+        "cup" => Ok(FixtureType::Cup),
+        x => Err(ParseError::UnknownFixtureType(x.to_owned())),
     }
 }
 
-fn parse_fixture_time(cell: &ElementRef) -> DateTime<Tz> {
+fn parse_fixture_time(cell: &ElementRef) -> Result<DateTime<Tz>, ParseError> {
     match cell
         .select(&Selector::parse("span").unwrap())
         .collect::<Vec<_>>()[..]
@@ -99,18 +331,19 @@ fn parse_fixture_time(cell: &ElementRef) -> DateTime<Tz> {
         [date, time] => {
             let date_time = format!("{} {}", date.inner_html().trim(), time.inner_html().trim());
             NaiveDateTime::parse_from_str(&date_time, "%d/%m/%y %H:%M")
-                .expect(&format!(
-                    "Invalid date time format when parsing {date_time}."
-                ))
+                .map_err(|_| ParseError::InvalidDateTime(date_time.clone()))?
                 .and_local_timezone(London)
                 .single()
-                .expect("Not a valid London time.")
+                .ok_or(ParseError::AmbiguousLocalTime(date_time))
         }
-        _ => panic!("Expected exactly two span elements when parsing date time."),
+        _ => Err(ParseError::MissingDateTime),
     }
 }
 
-fn try_parse_opposition(cell: &ElementRef, team_name: &TeamName) -> Option<String> {
+fn try_parse_opposition(
+    cell: &ElementRef,
+    team_name: &TeamName,
+) -> Result<Option<String>, ParseError> {
     match cell
         .select(&Selector::parse("a").unwrap())
         .flat_map(|x| x.text().map(|s| s.trim()))
@@ -118,12 +351,12 @@ fn try_parse_opposition(cell: &ElementRef, team_name: &TeamName) -> Option<Strin
     {
         [opposition] => {
             if opposition.eq_ignore_ascii_case(&team_name.0) {
-                None
+                Ok(None)
             } else {
-                Some(opposition.to_owned())
+                Ok(Some(opposition.to_owned()))
             }
         }
-        _ => panic!("Expected a single anchor tag when parsing team name."),
+        _ => Err(ParseError::InvalidTeamCell(cell_text(cell))),
     }
 }
 
@@ -131,83 +364,202 @@ fn parse_teams(
     home: &ElementRef,
     away: &ElementRef,
     team_name: &TeamName,
-) -> (FixtureSide, String) {
+) -> Result<(FixtureSide, String), ParseError> {
     match (
-        try_parse_opposition(home, team_name),
-        try_parse_opposition(away, team_name),
+        try_parse_opposition(home, team_name)?,
+        try_parse_opposition(away, team_name)?,
     ) {
-        (Some(opposition), None) => (FixtureSide::Away, opposition),
-        (None, Some(opposition)) => (FixtureSide::Home, opposition),
-        _ => panic!("Team wasn't listed as either home or away for this fixture."),
-    }
-}
-
-fn parse_venue(cell: &ElementRef) -> Venue {
-    let venue_name = cell.inner_html().trim().to_lowercase();
-    if venue_name.contains("goals") {
-        Venue::Goals
-    } else if venue_name.contains("woodford") {
-        if venue_name.contains("grass") {
-            Venue::WoodfordParkGrass
-        } else if venue_name.contains("3g") {
-            Venue::WoodfordPark3G
-        } else {
-            panic!("Unknown Woodford venue type {}", venue_name)
-        }
-    } else if venue_name.contains("kings academy") {
-        Venue::KingsAcademy
-    } else if venue_name.contains("cintra park") {
-        Venue::CintraPark
-    } else if venue_name.contains("north street theale") {
-        Venue::NorthStreetTheale
-    } else {
-        panic!("Unknown venue {}", venue_name)
+        (Some(opposition), None) => Ok((FixtureSide::Away, opposition)),
+        (None, Some(opposition)) => Ok((FixtureSide::Home, opposition)),
+        _ => Err(ParseError::TeamNotInFixture {
+            home: cell_text(home),
+            away: cell_text(away),
+        }),
+    }
+}
+
+fn parse_other_venue(cell: &ElementRef) -> Venue {
+    let mut lines = cell
+        .text()
+        .map(|s| s.trim())
+        .filter(|s| !s.is_empty())
+        .map(|s| s.to_owned());
+    Venue::Other {
+        name: lines.next().unwrap_or_default(),
+        address: lines.collect::<Vec<_>>().join(", "),
     }
 }
 
-fn parse_fixture<'a>(row: impl Iterator<Item = ElementRef<'a>>, team_name: &TeamName) -> Fixture {
-    let row = row.collect::<Vec<_>>();
-    match &row[..] {
+fn parse_venue(cell: &ElementRef, venues: &venue::Registry) -> Venue {
+    match venues.find_by_full_time_text(cell.inner_html().trim()) {
+        Some(known) => Venue::Known(known.name.clone()),
+        None => parse_other_venue(cell),
+    }
+}
+
+fn parse_fixture_id(row: &[ElementRef]) -> Option<FixtureId> {
+    let selector = Selector::parse("a[href*='displayFixture']").unwrap();
+    row.iter()
+        .flat_map(|cell| cell.select(&selector))
+        .filter_map(|a| a.value().attr("href"))
+        .find_map(|href| {
+            let (_, query) = href.split_once('?')?;
+            query
+                .split('&')
+                .find_map(|param| param.strip_prefix("id="))
+                .map(FixtureId::new)
+        })
+}
+
+/// The opposition of a row which may not otherwise parse.
+fn parse_row_opposition(row: &[ElementRef], team_name: &TeamName) -> Option<String> {
+    match row {
+        [_, _, home_team, _, _, _, away_team, _] => parse_teams(home_team, away_team, team_name)
+            .ok()
+            .map(|(_, opposition)| opposition),
+        _ => None,
+    }
+}
+
+fn parse_fixture(
+    row: &[ElementRef],
+    team_name: &TeamName,
+    venues: &venue::Registry,
+) -> Result<Fixture, ParseError> {
+    match row {
         [typ, date_time, home_team, _, _, _, away_team, venue] => {
-            let (fixture_side, opposition) = parse_teams(home_team, away_team, team_name);
-            Fixture {
-                typ: parse_fixture_type(typ),
+            let (fixture_side, opposition) = parse_teams(home_team, away_team, team_name)?;
+            Ok(Fixture {
+                id: parse_fixture_id(row),
+                typ: parse_fixture_type(typ)?,
                 side: fixture_side,
-                date_time: parse_fixture_time(date_time),
+                date_time: parse_fixture_time(date_time)?,
                 opposition,
-                venue: parse_venue(venue),
-            }
+                venue: parse_venue(venue, venues),
+            })
         }
-        _ => panic!("Incorrect number of cells in table row."),
+        _ => Err(ParseError::IncorrectCellCount(row.len())),
     }
 }
 
-pub async fn get_upcoming_fixtures(
-    season_id: SeasonId,
-    team: &Team,
-) -> Result<Vec<Fixture>, Error> {
-    let url = format!(
-        "https://fulltime.thefa.com/displayTeam.html?divisionseason={}&teamID={}",
-        *season_id, *team.id
-    );
-    let html = reqwest::get(url).await?.text().await?;
-    let document = Html::parse_document(&html);
+/// Parses the fixtures table of a Full-Time `displayTeam.html` page, keeping
+/// those which kick off at or after `now`. Rows which can't be parsed are
+/// returned as skipped rather than failing the whole page.
+pub fn parse_upcoming_fixtures(
+    html: &str,
+    team_name: &TeamName,
+    venues: &venue::Registry,
+    now: DateTime<Utc>,
+) -> Result<UpcomingFixtures, ParseError> {
+    let document = Html::parse_document(html);
     let tables = document
         .select(&Selector::parse("div.fixtures-table table").unwrap())
         .collect::<Vec<_>>();
     let table = match tables[..] {
-        [] => return Ok(vec![]),
+        [] => return Ok(UpcomingFixtures::default()),
         [t] => t,
-        _ => panic!(
-            "Expected to find one fixture table, but found {}.",
-            tables.len()
-        ),
+        _ => return Err(ParseError::MultipleFixtureTables(tables.len())),
     };
     let td_selector = Selector::parse("td").unwrap();
-    Ok(table
+    let mut upcoming = UpcomingFixtures::default();
+    for (index, tr) in table
         .select(&Selector::parse("tbody tr").unwrap())
-        .map(|tr| tr.select(&td_selector))
-        .map(|r| parse_fixture(r, &team.name))
-        .filter(|f| f.date_time.with_timezone(&Utc) >= Utc::now())
-        .collect::<Vec<_>>())
+        .enumerate()
+    {
+        let cells = tr.select(&td_selector).collect::<Vec<_>>();
+        match parse_fixture(&cells, team_name, venues) {
+            Ok(fixture) => {
+                if fixture.date_time.with_timezone(&Utc) >= now {
+                    upcoming.fixtures.push(fixture);
+                }
+            }
+            Err(reason) => upcoming.skipped.push(SkippedRow {
+                row: index,
+                text: cell_text(&tr),
+                reason,
+                id: parse_fixture_id(&cells),
+                opposition: parse_row_opposition(&cells, team_name),
+            }),
+        }
+    }
+    Ok(upcoming)
+}
+
+pub const DEFAULT_BASE_URL: &str = "https://fulltime.thefa.com";
+
+/// A client for scraping fixtures from Full-Time.
+///
+/// A `file://` base URL reads saved pages from that directory instead, named
+/// after the team id, e.g. `file:///tmp/pages` reads `/tmp/pages/12345.html`.
+#[derive(Debug, Clone)]
+pub struct FullTimeClient {
+    http: reqwest::Client,
+    base_url: String,
+    sender: http::Sender,
+}
+
+impl Default for FullTimeClient {
+    fn default() -> Self {
+        Self::new(DEFAULT_BASE_URL)
+    }
+}
+
+impl FullTimeClient {
+    pub fn new(base_url: impl Into<String>) -> Self {
+        Self::with_http_client(http::client(http::DEFAULT_TIMEOUT), base_url)
+    }
+
+    pub fn with_http_client(http: reqwest::Client, base_url: impl Into<String>) -> Self {
+        Self {
+            http,
+            base_url: base_url.into().trim_end_matches('/').to_owned(),
+            sender: http::Sender::default(),
+        }
+    }
+
+    pub fn with_sender(self, sender: http::Sender) -> Self {
+        Self { sender, ..self }
+    }
+
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    async fn get_team_page(&self, season_id: SeasonId, team: &Team) -> Result<String, Error> {
+        match self.base_url.strip_prefix("file://") {
+            Some(dir) => Ok(tokio::fs::read_to_string(
+                std::path::Path::new(dir).join(format!("{}.html", *team.id)),
+            )
+            .await?),
+            None => Ok(self
+                .sender
+                .send(
+                    self.http
+                        .get(format!("{}/displayTeam.html", self.base_url))
+                        .query(&[
+                            ("divisionseason", season_id.to_string()),
+                            ("teamID", team.id.to_string()),
+                        ]),
+                )
+                .await?
+                .error_for_status()?
+                .text()
+                .await?),
+        }
+    }
+
+    pub async fn get_upcoming_fixtures(
+        &self,
+        season_id: SeasonId,
+        team: &Team,
+        venues: &venue::Registry,
+    ) -> Result<UpcomingFixtures, Error> {
+        let html = self.get_team_page(season_id, team).await?;
+        Ok(parse_upcoming_fixtures(
+            &html,
+            &team.name,
+            venues,
+            Utc::now(),
+        )?)
+    }
 }
@@ -0,0 +1,206 @@
+use std::{sync::Arc, time::Duration};
+
+use chrono::{DateTime, Utc};
+use rand::Rng;
+use reqwest::{header::RETRY_AFTER, RequestBuilder, Response, StatusCode};
+use tokio::{sync::Mutex, time::Instant};
+
+/// How long a request may take, including reading its response, before it
+/// fails and can be retried.
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How long connecting to a server may take.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A client whose requests time out, so that a server which stops responding
+/// fails the request rather than hanging the sync.
+pub fn client(timeout: Duration) -> reqwest::Client {
+    reqwest::Client::builder()
+        .timeout(timeout)
+        .connect_timeout(CONNECT_TIMEOUT.min(timeout))
+        .build()
+        .expect("the HTTP client's TLS backend should initialise")
+}
+
+/// How requests which fail with a timeout, a connection error, 429 or a 5xx
+/// status are retried.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryPolicy {
+    /// The number of times a request is sent before giving up, including the first.
+    pub attempts: u32,
+    /// The delay before the first retry, which doubles for each retry after it.
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    /// The fraction of each backoff which is randomised, from 0 to 1, so that
+    /// clients which failed together don't retry together.
+    pub jitter: f64,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            attempts: 4,
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(30),
+            jitter: 0.5,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Sends each request once.
+    pub fn none() -> Self {
+        Self {
+            attempts: 1,
+            ..Self::default()
+        }
+    }
+
+    /// The delay before the given retry, counting from 1, without jitter.
+    fn backoff(&self, retry: u32) -> Duration {
+        self.initial_backoff
+            .saturating_mul(2u32.saturating_pow(retry.saturating_sub(1)))
+            .min(self.max_backoff)
+    }
+
+    fn jittered_backoff(&self, retry: u32) -> Duration {
+        let backoff = self.backoff(retry);
+        let jitter = self.jitter.clamp(0.0, 1.0);
+        backoff.mul_f64(1.0 - jitter * rand::thread_rng().gen::<f64>())
+    }
+}
+
+/// Spaces requests out so that no more than a given number are sent per second.
+#[derive(Debug)]
+pub struct RateLimit {
+    interval: Duration,
+    next: Mutex<Instant>,
+}
+
+impl RateLimit {
+    pub fn per_second(requests: f64) -> Self {
+        Self {
+            interval: if requests > 0.0 {
+                Duration::from_secs_f64(1.0 / requests)
+            } else {
+                Duration::ZERO
+            },
+            next: Mutex::new(Instant::now()),
+        }
+    }
+
+    pub fn unlimited() -> Self {
+        Self::per_second(0.0)
+    }
+
+    /// Waits until the next request may be sent.
+    async fn wait(&self) {
+        let mut next = self.next.lock().await;
+        let now = Instant::now();
+        if *next > now {
+            tokio::time::sleep_until(*next).await;
+        }
+        *next = (*next).max(now) + self.interval;
+    }
+}
+
+/// The delay a 429 or 503 response asks for, given in seconds or as an HTTP date.
+fn retry_after(response: &Response) -> Option<Duration> {
+    let value = response.headers().get(RETRY_AFTER)?.to_str().ok()?.trim();
+    match value.parse::<u64>() {
+        Ok(seconds) => Some(Duration::from_secs(seconds)),
+        Err(_) => (DateTime::parse_from_rfc2822(value).ok()? - Utc::now().fixed_offset())
+            .to_std()
+            .ok(),
+    }
+}
+
+fn should_retry(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+}
+
+/// Sends a client's requests, retrying them according to its policy and
+/// limiting the rate all of its requests are sent at, including retries.
+#[derive(Debug, Clone)]
+pub struct Sender {
+    retry: RetryPolicy,
+    rate_limit: Arc<RateLimit>,
+}
+
+impl Default for Sender {
+    fn default() -> Self {
+        Self::new(RetryPolicy::default(), RateLimit::unlimited())
+    }
+}
+
+impl Sender {
+    pub fn new(retry: RetryPolicy, rate_limit: RateLimit) -> Self {
+        Self {
+            retry,
+            rate_limit: Arc::new(rate_limit),
+        }
+    }
+
+    /// Sends requests with `retry` instead, sharing this sender's rate limit.
+    pub fn with_retry(&self, retry: RetryPolicy) -> Self {
+        Self {
+            retry,
+            rate_limit: self.rate_limit.clone(),
+        }
+    }
+
+    /// Sends a request, returning the last response once it succeeds or the
+    /// attempts run out. Requests whose body can't be cloned are only sent once.
+    pub async fn send(&self, request: RequestBuilder) -> reqwest::Result<Response> {
+        let mut retry = 0;
+        loop {
+            let attempt = match request.try_clone() {
+                Some(attempt) if retry + 1 < self.retry.attempts => attempt,
+                _ => {
+                    self.rate_limit.wait().await;
+                    return request.send().await;
+                }
+            };
+            self.rate_limit.wait().await;
+            retry += 1;
+            let delay = match attempt.send().await {
+                Ok(response) if should_retry(response.status()) => retry_after(&response)
+                    .map(|delay| delay.min(self.retry.max_backoff))
+                    .unwrap_or_else(|| self.retry.jittered_backoff(retry)),
+                Err(e) if e.is_timeout() || e.is_connect() => self.retry.jittered_backoff(retry),
+                result => return result,
+            };
+            tokio::time::sleep(delay).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn doubles_the_backoff_up_to_the_maximum() {
+        let policy = RetryPolicy {
+            attempts: 10,
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(5),
+            jitter: 0.0,
+        };
+        assert_eq!(
+            (1..=5)
+                .map(|r| policy.jittered_backoff(r))
+                .collect::<Vec<_>>(),
+            [1, 2, 4, 5, 5].map(Duration::from_secs)
+        );
+    }
+
+    #[test]
+    fn jitter_only_shortens_the_backoff() {
+        let policy = RetryPolicy {
+            jitter: 1.0,
+            ..RetryPolicy::default()
+        };
+        assert!((0..100).all(|_| policy.jittered_backoff(2) <= policy.backoff(2)));
+    }
+}
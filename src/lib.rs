@@ -1,25 +1,62 @@
 use chrono::{DateTime, Datelike, Duration, Utc};
-use chrono_tz::Europe::London;
+use chrono_tz::{Europe::London, Tz};
 use itertools::Itertools;
+use serde::{Deserialize, Serialize};
 use spond::SubGroup;
-use std::collections::HashMap;
 
+pub mod error;
 pub mod full_time;
+pub mod http;
+pub mod plan;
+pub mod snapshot;
 pub mod spond;
+pub mod state;
 pub mod team;
+pub mod undo;
+pub mod venue;
+pub use error::{Error, SpondApiError};
 pub use team::Team;
 
+/// Prefixes the line in a Spond's description which records the key of the
+/// Full-Time fixture it was created from.
+const FIXTURE_KEY_PREFIX: &str = "Full-Time fixture: ";
+
+fn description_with_fixture_key(description: Option<&str>, key: &full_time::FixtureKey) -> String {
+    description
+        .unwrap_or_default()
+        .lines()
+        .filter(|line| !line.starts_with(FIXTURE_KEY_PREFIX))
+        .chain(std::iter::once(
+            format!("{FIXTURE_KEY_PREFIX}{}", &**key).as_str(),
+        ))
+        .join("\n")
+}
+
 impl spond::Spond {
-    fn to_fixture(&self) -> Option<full_time::Fixture> {
+    fn fixture_key(&self) -> Option<full_time::FixtureKey> {
+        self.description
+            .as_deref()?
+            .lines()
+            .find_map(|line| line.strip_prefix(FIXTURE_KEY_PREFIX))
+            .map(|key| full_time::FixtureKey::new(key.trim()))
+    }
+
+    /// The key the Spond's fixture would have without an id.
+    fn opposition_key(&self) -> Option<full_time::FixtureKey> {
+        self.match_info.as_ref().map(|match_info| {
+            full_time::Fixture::key_for_opposition(
+                &fixture_type(&match_info.typ),
+                &match_info.opponent_name,
+            )
+        })
+    }
+
+    fn to_fixture(&self, venues: &venue::Registry) -> Option<full_time::Fixture> {
         self.match_info
             .as_ref()
             .map(|match_info| full_time::Fixture {
-                typ: match match_info.typ {
-                    spond::MatchType::Tournament => full_time::FixtureType::Cup,
-                    spond::MatchType::Home | spond::MatchType::Away => {
-                        full_time::FixtureType::League
-                    }
-                },
+                id: self.fixture_key().and_then(|key| key.fixture_id()),
+                typ: fixture_type(&match_info.typ),
                 side: match match_info.typ {
                     spond::MatchType::Tournament | spond::MatchType::Home => {
                         full_time::FixtureSide::Home
@@ -28,15 +65,25 @@ impl spond::Spond {
                 },
                 date_time: self.start_timestamp.with_timezone(&London),
                 opposition: match_info.opponent_name.clone(),
-                venue: self
-                    .location
-                    .as_ref()
-                    .expect("All fixtures should have a location")
-                    .to_full_time_venue(),
+                // A coach may have cleared the location, which syncing restores.
+                venue: self.location.as_ref().map_or_else(
+                    || full_time::Venue::Other {
+                        name: String::new(),
+                        address: String::new(),
+                    },
+                    |location| location.to_full_time_venue(venues),
+                ),
             })
     }
 }
 
+fn fixture_type(match_type: &spond::MatchType) -> full_time::FixtureType {
+    match match_type {
+        spond::MatchType::Tournament => full_time::FixtureType::Cup,
+        spond::MatchType::Home | spond::MatchType::Away => full_time::FixtureType::League,
+    }
+}
+
 impl full_time::Fixture {
     fn to_spond_start_time(&self) -> DateTime<Utc> {
         self.date_time.with_timezone(&Utc)
@@ -63,6 +110,19 @@ impl full_time::Fixture {
         }
     }
 
+    /// Identifies the request creating this fixture's Spond for a team, so that
+    /// a create which is retried after its response was lost isn't duplicated.
+    fn idempotency_key(&self, team: &Team) -> spond::IdempotencyKey {
+        spond::IdempotencyKey::new(
+            &team.spond.sub_group_id,
+            &format!(
+                "{}@{}",
+                &*self.key(),
+                self.to_spond_start_time().to_rfc3339()
+            ),
+        )
+    }
+
     fn to_spond_match_info(&self, sub_group: &SubGroup) -> spond::MatchInfo {
         spond::MatchInfo::new(
             sub_group.name.clone(),
@@ -81,6 +141,7 @@ impl full_time::Fixture {
         &self,
         group: &spond::Group,
         sub_group_id: &spond::SubGroupId,
+        venues: &venue::Registry,
     ) -> spond::CreateSpondRequest {
         let sub_group = group
             .sub_groups
@@ -95,7 +156,7 @@ impl full_time::Fixture {
         let sub_group_members = group
             .members
             .iter()
-            .filter(|m| m.sub_groups.contains(&sub_group_id));
+            .filter(|m| m.sub_groups.contains(sub_group_id));
         let coaches = sub_group_members
             .clone()
             .filter(|m| {
@@ -108,6 +169,7 @@ impl full_time::Fixture {
         let players = sub_group_members.filter(|m| m.respondent);
         spond::CreateSpondRequest {
             heading: format!("{} - {}", sub_group.name, self.opposition),
+            description: Some(description_with_fixture_key(None, &self.key())),
             spond_type: spond::SpondType::Event,
             start_timestamp: self.to_spond_start_time(),
             end_timestamp: self.to_spond_end_time(),
@@ -116,7 +178,7 @@ impl full_time::Fixture {
             comments_disabled: false,
             max_accepted: 0,
             rsvp_date: None,
-            location: Some(spond::Location::from_full_time_venue(self.venue)),
+            location: Some(spond::Location::from_full_time_venue(&self.venue, venues)),
             owners: coaches
                 .filter_map(|c| c.map(|c| spond::Owner { id: c.id }))
                 .collect(),
@@ -144,6 +206,7 @@ impl spond::Spond {
         fixture: &full_time::Fixture,
         group: &spond::Group,
         sub_group_id: &spond::SubGroupId,
+        venues: &venue::Registry,
     ) -> Self {
         let sub_group = group
             .sub_groups
@@ -151,161 +214,704 @@ impl spond::Spond {
             .find(|sg| sg.id == *sub_group_id)
             .unwrap();
         Self {
+            description: Some(description_with_fixture_key(
+                self.description.as_deref(),
+                &fixture.key(),
+            )),
             start_timestamp: fixture.to_spond_start_time(),
             end_timestamp: fixture.to_spond_end_time(),
             meetup_prior: fixture.to_spond_meetup_prior(),
             match_info: Some(fixture.to_spond_match_info(sub_group)),
-            location: Some(spond::Location::from_full_time_venue(fixture.venue)),
+            location: Some(spond::Location::from_full_time_venue(
+                &fixture.venue,
+                venues,
+            )),
             ..(self.clone())
         }
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+/// The changes needed to bring a team's Sponds in line with its Full-Time
+/// fixtures. Each modified pair is serialised as `{"fixture": ..., "spond": ...}`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Diff {
     pub new: Vec<full_time::Fixture>,
+    #[serde(
+        serialize_with = "serialize_modified",
+        deserialize_with = "deserialize_modified"
+    )]
     pub modified: Vec<(full_time::Fixture, spond::Spond)>,
     pub removed: Vec<spond::Spond>,
 }
 
+fn serialize_modified<S: serde::Serializer>(
+    modified: &[(full_time::Fixture, spond::Spond)],
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    #[derive(Serialize)]
+    struct Modified<'a> {
+        fixture: &'a full_time::Fixture,
+        spond: &'a spond::Spond,
+    }
+
+    serializer.collect_seq(
+        modified
+            .iter()
+            .map(|(fixture, spond)| Modified { fixture, spond }),
+    )
+}
+
+fn deserialize_modified<'de, D: serde::Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<(full_time::Fixture, spond::Spond)>, D::Error> {
+    #[derive(Deserialize)]
+    struct Modified {
+        fixture: full_time::Fixture,
+        spond: spond::Spond,
+    }
+
+    Ok(Vec::<Modified>::deserialize(deserializer)?
+        .into_iter()
+        .map(|m| (m.fixture, m.spond))
+        .collect())
+}
+
 impl Diff {
-    pub fn new(fixtures: Vec<full_time::Fixture>, sponds: Vec<spond::Spond>) -> Self {
-        let fixtures = fixtures
-            .into_iter()
-            .map(|f| (f.date_time.date_naive(), f))
-            .collect::<HashMap<_, _>>();
-        let sponds = sponds
+    /// Pairs each fixture with the Spond that was created for it, using the
+    /// fixture key stored in the Spond's description, or failing that the
+    /// fixture the Spond was last synced with. A fixture which has gained or
+    /// lost its id since is paired by its type and opposition instead. Sponds
+    /// created before the key was recorded are paired with a fixture on the
+    /// same date.
+    pub fn new(
+        fixtures: Vec<full_time::Fixture>,
+        sponds: Vec<spond::Spond>,
+        venues: &venue::Registry,
+        records: &state::Records,
+    ) -> Self {
+        let key_of = |s: &spond::Spond| {
+            s.fixture_key()
+                .or_else(|| records.get(&s.id).map(|r| r.fixture.key()))
+        };
+        let (keyed, legacy): (Vec<_>, Vec<_>) = sponds
             .into_iter()
             .filter(|s| {
-                !s.match_info
-                    .as_ref()
-                    .is_some_and(|m| m.opponent_name == "BYFL Summer Tournament".to_owned())
+                !s.cancelled
+                    && s.match_info
+                        .as_ref()
+                        .is_none_or(|m| m.opponent_name != "BYFL Summer Tournament")
             })
-            .map(|s| (s.start_timestamp.with_timezone(&London).date_naive(), s))
-            .collect::<HashMap<_, _>>();
+            .sorted_by_key(|s| s.start_timestamp)
+            .partition(|s| key_of(s).is_some());
+        let mut keyed = keyed.into_iter().into_group_map_by(key_of);
+        let mut legacy = legacy
+            .into_iter()
+            .into_group_map_by(|s| s.start_timestamp.with_timezone(&London).date_naive());
+
+        let mut unmatched = vec![];
+        let mut matched = vec![];
+        let fixtures = fixtures.into_iter().sorted_by_key(|f| f.date_time);
+        for fixture in fixtures {
+            match keyed
+                .get_mut(&Some(fixture.key()))
+                .and_then(|sponds| (!sponds.is_empty()).then(|| sponds.remove(0)))
+            {
+                Some(spond) => matched.push((fixture, spond)),
+                None => unmatched.push(fixture),
+            }
+        }
+
+        // Only fixtures which weren't matched by key fall back, so that a
+        // fixture can't take the Spond of another which is still listed.
+        let mut keyed = keyed
+            .into_values()
+            .flatten()
+            .sorted_by_key(|s| s.start_timestamp)
+            .collect::<Vec<_>>();
+        let mut new = vec![];
+        for fixture in unmatched {
+            let by_opposition = keyed.iter().position(|s| {
+                let Some(key) = key_of(s) else { return false };
+                match (&fixture.id, key.fixture_id()) {
+                    // The fixture has gained an id since the Spond was keyed by its opposition.
+                    (Some(_), None) => key == fixture.opposition_key(),
+                    // The fixture has lost its id.
+                    (None, Some(_)) => s.opposition_key() == Some(fixture.key()),
+                    _ => false,
+                }
+            });
+            let spond = by_opposition.map(|i| keyed.remove(i)).or_else(|| {
+                legacy
+                    .get_mut(&fixture.date_time.date_naive())
+                    .and_then(|sponds| (!sponds.is_empty()).then(|| sponds.remove(0)))
+            });
+            match spond {
+                Some(spond) => matched.push((fixture, spond)),
+                None => new.push(fixture),
+            }
+        }
+
         Self {
-            new: fixtures
-                .iter()
-                .filter(|f| !sponds.contains_key(f.0))
-                .map(|f| f.1.to_owned())
-                .sorted_by_key(|f| f.date_time)
-                .collect(),
-            modified: fixtures
-                .iter()
-                .filter_map(|(date, fixture)| {
-                    sponds
-                        .get(date)
-                        .map(|spond| (fixture.clone(), spond.clone()))
-                })
+            new,
+            modified: matched
+                .into_iter()
                 .filter(|(fixture, spond)| {
-                    !(spond.to_fixture().is_some_and(|s| s == *fixture)
-                        && fixture.to_spond_meetup_prior() == spond.meetup_prior)
+                    !(spond.to_fixture(venues).is_some_and(|s| s == *fixture)
+                        && fixture.to_spond_meetup_prior() == spond.meetup_prior
+                        && spond.fixture_key() == Some(fixture.key()))
                 })
-                .sorted_by_key(|(f, _)| f.date_time)
                 .collect(),
-            removed: sponds
-                .iter()
-                .filter(|f| !fixtures.contains_key(f.0))
-                .sorted_by_key(|(date_time, _)| **date_time)
-                .map(|f| f.1.to_owned())
+            removed: keyed
+                .into_iter()
+                .chain(legacy.into_values().flatten())
+                .sorted_by_key(|s| s.start_timestamp)
                 .collect(),
         }
     }
 }
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
 pub enum SyncType {
     Dry,
     Real,
 }
 
-pub async fn sync(
+/// A field of a fixture which differs between its Spond and Full-Time.
+/// Serialised with a `field` tag, e.g. `{"field": "kick_off", "before": ..., "after": ...}`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "field", rename_all = "snake_case")]
+pub enum FixtureChange {
+    KickOff {
+        before: DateTime<Tz>,
+        after: DateTime<Tz>,
+    },
+    Venue {
+        before: full_time::Venue,
+        after: full_time::Venue,
+    },
+    Opposition {
+        before: String,
+        after: String,
+    },
+    Side {
+        before: full_time::FixtureSide,
+        after: full_time::FixtureSide,
+    },
+    Competition {
+        before: full_time::FixtureType,
+        after: full_time::FixtureType,
+    },
+    /// The number of minutes before kick off to meet up.
+    Meetup {
+        before: Option<u16>,
+        after: Option<u16>,
+    },
+}
+
+impl FixtureChange {
+    /// Lists the fields which differ between the fixture a Spond currently
+    /// represents and the fixture on Full-Time.
+    pub fn between(
+        before: &full_time::Fixture,
+        before_meetup_prior: Option<u16>,
+        after: &full_time::Fixture,
+    ) -> Vec<Self> {
+        let mut changes = vec![];
+        if before.date_time != after.date_time {
+            changes.push(Self::KickOff {
+                before: before.date_time,
+                after: after.date_time,
+            });
+        }
+        if before.venue != after.venue {
+            changes.push(Self::Venue {
+                before: before.venue.clone(),
+                after: after.venue.clone(),
+            });
+        }
+        if before.opposition != after.opposition {
+            changes.push(Self::Opposition {
+                before: before.opposition.clone(),
+                after: after.opposition.clone(),
+            });
+        }
+        if before.side != after.side {
+            changes.push(Self::Side {
+                before: before.side,
+                after: after.side,
+            });
+        }
+        if before.typ != after.typ {
+            changes.push(Self::Competition {
+                before: before.typ.clone(),
+                after: after.typ.clone(),
+            });
+        }
+        if before_meetup_prior != after.to_spond_meetup_prior() {
+            changes.push(Self::Meetup {
+                before: before_meetup_prior,
+                after: after.to_spond_meetup_prior(),
+            });
+        }
+        changes
+    }
+}
+
+impl std::fmt::Display for FixtureChange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        fn meetup(prior: &Option<u16>) -> String {
+            prior.map_or("none".to_owned(), |p| format!("{p} mins before"))
+        }
+
+        match self {
+            FixtureChange::KickOff { before, after } => write!(
+                f,
+                "kick-off {} -> {}",
+                before.format("%a %d/%m/%y %H:%M"),
+                after.format("%a %d/%m/%y %H:%M")
+            ),
+            FixtureChange::Venue { before, after } => write!(f, "venue {before} -> {after}"),
+            FixtureChange::Opposition { before, after } => {
+                write!(f, "opposition {before} -> {after}")
+            }
+            FixtureChange::Side { before, after } => {
+                write!(f, "home/away {before:?} -> {after:?}")
+            }
+            FixtureChange::Competition { before, after } => {
+                write!(f, "competition {before:?} -> {after:?}")
+            }
+            FixtureChange::Meetup { before, after } => {
+                write!(f, "meetup {} -> {}", meetup(before), meetup(after))
+            }
+        }
+    }
+}
+
+/// A Spond which was, or for a dry run would be, updated to match its fixture.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Update {
+    /// The Spond before it was updated.
+    pub spond: spond::Spond,
+    pub before: Option<full_time::Fixture>,
+    pub after: full_time::Fixture,
+    pub changes: Vec<FixtureChange>,
+}
+
+/// A Spond whose fixture is no longer on Full-Time, and which was, or for a
+/// dry run would be, handled according to the team's removal policy.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Deletion {
+    pub spond: spond::Spond,
+    pub fixture: Option<full_time::Fixture>,
+    pub removal: team::Removal,
+}
+
+/// What to do with a Spond whose Full-Time fields have been edited in Spond
+/// since sync last wrote them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OnEdit {
+    /// Leave the Spond as it is and report the edits.
+    #[default]
+    Skip,
+    /// Overwrite the edits and report them.
+    Warn,
+    /// Fail the team's sync without changing anything.
+    Refuse,
+    /// Overwrite the edits, e.g. when forced to.
+    Overwrite,
+}
+
+/// A Spond with fields which have been edited in Spond since sync last wrote them.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Edited {
+    pub spond: spond::Spond,
+    pub fields: Vec<snapshot::Field>,
+    /// Whether the edits were, or for a dry run would be, overwritten.
+    pub overwritten: bool,
+}
+
+impl std::fmt::Display for Edited {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} has edits to its {} in Spond",
+            self.spond.heading,
+            self.fields.iter().join(", ")
+        )
+    }
+}
+
+/// What a sync did for a team, or for a dry run what it would have done.
+///
+/// This is the `--output json` schema: fixtures are serialised as
+/// [`full_time::Fixture`] and Sponds in the Spond API's own format.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SyncReport {
+    pub team: String,
+    pub sync_type: SyncType,
+    pub upcoming_full_time: usize,
+    pub upcoming_spond: usize,
+    pub created: Vec<full_time::Fixture>,
+    pub updated: Vec<Update>,
+    pub deleted: Vec<Deletion>,
+    /// Sponds whose fixture is no longer on Full-Time, left in place because
+    /// the team's removal policy is to only report them.
+    pub reported: Vec<Deletion>,
+    /// Sponds whose fixture changed but which were also edited in Spond.
+    pub edited: Vec<Edited>,
+    /// Rows of the Full-Time fixtures table which couldn't be parsed.
+    pub skipped: Vec<full_time::SkippedRow>,
+    /// Sponds whose fixture wasn't found on Full-Time but which were kept
+    /// because rows were skipped, so their fixture may be one of those rows.
+    pub kept: Vec<spond::Spond>,
+    pub errors: Vec<String>,
+    /// Why Spond rejected the requests behind any of the errors.
+    pub spond_errors: Vec<SpondApiError>,
+}
+
+impl SyncReport {
+    pub fn new(team: impl Into<String>, sync_type: SyncType) -> Self {
+        Self {
+            team: team.into(),
+            sync_type,
+            upcoming_full_time: 0,
+            upcoming_spond: 0,
+            created: vec![],
+            updated: vec![],
+            deleted: vec![],
+            reported: vec![],
+            edited: vec![],
+            skipped: vec![],
+            kept: vec![],
+            errors: vec![],
+            spond_errors: vec![],
+        }
+    }
+
+    /// Records a failure, keeping the details of Spond's response if it rejected a request.
+    pub fn push_error(&mut self, message: String, error: Error) {
+        if let Error::SpondApi(e) | Error::SpondAuth(e) = error {
+            self.spond_errors.push(e);
+        }
+        self.errors.push(message);
+    }
+}
+
+/// Applies a diff to a team's Sponds, recording what was written in `state`.
+/// For a dry run nothing is sent to Spond and the report describes what would
+/// have been done.
+pub async fn apply(
     team: &Team,
-    spond_creds: &spond::UserCredentials,
-    spond_group_id: spond::GroupId,
+    venues: &venue::Registry,
+    spond_client: &spond::SpondClient,
+    state: &state::State,
+    diff: Diff,
+    on_edit: OnEdit,
     sync_type: SyncType,
-) -> Result<(), Box<dyn std::error::Error>> {
-    let full_times_fixtures =
-        full_time::get_upcoming_fixtures(team.full_time.season_id, &team.full_time.team).await?;
-
-    let spond_session = spond::login(&spond_creds).await?;
-    let spond_group = spond::get_group(&spond_group_id, &spond_session).await?;
-    let mut spond_fixtures =
-        spond::get_upcoming_matches(&spond_group_id, &team.spond.sub_group_id, &spond_session)
-            .await?;
-    spond_fixtures.sort_by_key(|f| f.start_timestamp);
-    let diff = Diff::new(full_times_fixtures.clone(), spond_fixtures.clone());
-
-    match sync_type {
-        SyncType::Dry => {
-            println!("Fixture diff for {}:", team.name);
-            println!(
-                "Upcoming Spond: {:#?}\nUpcoming full time: {:#?}\n",
-                spond_fixtures.len(),
-                full_times_fixtures.len(),
-            );
-            println!("{} new fixtures:", diff.new.len());
-            for fixture in diff.new.iter() {
-                println!("{:?}", fixture);
-            }
+) -> Result<SyncReport, Error> {
+    let mut report = SyncReport::new(&team.name, sync_type);
+    let records = state.records(&team.name)?;
 
-            println!("{} modified fixtures:", diff.modified.len(),);
-            for (fixture, spond_fixture) in diff.modified.iter() {
-                println!(
-                    "Current: {}",
-                    spond_fixture
-                        .to_fixture()
-                        .map_or("".to_owned(), |f| format!("{f:?}"))
-                );
-                println!("New    : {:?}", fixture);
-                println!();
-            }
+    let mut modified = vec![];
+    for (fixture, spond_fixture) in diff.modified {
+        let fields = records
+            .get(&spond_fixture.id)
+            .map_or_else(Vec::new, |record| {
+                record.snapshot.edited_fields(&spond_fixture)
+            });
+        if fields.is_empty() {
+            modified.push((fixture, spond_fixture));
+            continue;
+        }
+        let overwritten = matches!(on_edit, OnEdit::Warn | OnEdit::Overwrite);
+        report.edited.push(Edited {
+            spond: spond_fixture.clone(),
+            fields,
+            overwritten,
+        });
+        if overwritten {
+            modified.push((fixture, spond_fixture));
+        }
+    }
+    if on_edit == OnEdit::Refuse && !report.edited.is_empty() {
+        return Err(Error::Edited(report.edited));
+    }
 
-            println!("{} removed fixtures:", diff.removed.len());
-            for spond_fixture in diff.removed.iter() {
-                println!("{:?}", spond_fixture.to_fixture());
+    let spond_group = match sync_type {
+        SyncType::Dry => None,
+        SyncType::Real => Some(spond_client.get_group(&team.spond.group_id).await?),
+    };
+    let journal_change =
+        |action, spond_id: &spond::SpondId, spond_before: Option<&spond::Spond>, before, after| {
+            state.journal_change(
+                &state::Change {
+                    time: Utc::now(),
+                    operator: spond_client.email().unwrap_or("unknown").to_owned(),
+                    team: team.name.clone(),
+                    spond_id: spond_id.clone(),
+                    action,
+                    before,
+                    after,
+                },
+                spond_before,
+            )
+        };
+
+    // Each change is made independently, so that one which fails is reported
+    // without stopping the rest of the team's changes from being made. A change
+    // which is made in Spond but can't be recorded locally is still reported as
+    // made, with an error saying it wasn't recorded.
+    for fixture in diff.new {
+        if let Some(spond_group) = &spond_group {
+            let request =
+                fixture.to_create_spond_request(spond_group, &team.spond.sub_group_id, venues);
+            let spond = match spond_client
+                .create_spond(request.clone(), &fixture.idempotency_key(team))
+                .await
+            {
+                Ok(spond) => spond,
+                Err(e) => {
+                    report.push_error(
+                        format!(
+                            "Failed to create a Spond for {} on {}: {e}",
+                            fixture.opposition,
+                            fixture.date_time.format("%a %d/%m/%y %H:%M")
+                        ),
+                        e,
+                    );
+                    continue;
+                }
+            };
+            let recorded = state
+                .record(&team.name, &spond, &fixture, &request)
+                .and_then(|()| {
+                    journal_change(
+                        state::Action::Create,
+                        &spond.id,
+                        None,
+                        None,
+                        Some(fixture.clone()),
+                    )
+                });
+            if let Err(e) = recorded {
+                report.push_error(
+                    format!("Created {} but failed to record it: {e}", spond.heading),
+                    e.into(),
+                );
             }
-            println!();
         }
-        SyncType::Real => {
-            println!("Creating {} new fixtures for {}", diff.new.len(), team.name);
-            for fixture in diff.new.iter() {
-                println!("{:?}", fixture);
-                let spond = fixture.to_create_spond_request(&spond_group, &team.spond.sub_group_id);
-                spond::create_spond(spond, &spond_session).await?;
-            }
+        report.created.push(fixture);
+    }
 
-            println!(
-                "Updating {} modified fixtures for {}",
-                diff.modified.len(),
-                team.name
-            );
-            for (fixture, spond_fixture) in diff.modified.iter() {
-                println!("{:?}", fixture);
-                spond::update_spond(
-                    spond_fixture.modify(&fixture, &spond_group, &team.spond.sub_group_id),
-                    &spond_session,
-                )
-                .await?;
+    for (fixture, spond_fixture) in modified {
+        if let Some(spond_group) = &spond_group {
+            let spond =
+                spond_fixture.modify(&fixture, spond_group, &team.spond.sub_group_id, venues);
+            if let Err(e) = spond_client.update_spond(spond.clone()).await {
+                report.push_error(
+                    format!("Failed to update {}: {e}", spond_fixture.heading),
+                    e,
+                );
+                continue;
+            }
+            let recorded = state
+                .record(&team.name, &spond, &fixture, &spond)
+                .and_then(|()| {
+                    journal_change(
+                        state::Action::Update,
+                        &spond.id,
+                        Some(&spond_fixture),
+                        spond_fixture.to_fixture(venues),
+                        Some(fixture.clone()),
+                    )
+                });
+            if let Err(e) = recorded {
+                report.push_error(
+                    format!(
+                        "Updated {} but failed to record it: {e}",
+                        spond_fixture.heading
+                    ),
+                    e.into(),
+                );
             }
+        }
+        let before = spond_fixture.to_fixture(venues);
+        report.updated.push(Update {
+            changes: before.as_ref().map_or_else(Vec::new, |before| {
+                FixtureChange::between(before, spond_fixture.meetup_prior, &fixture)
+            }),
+            before,
+            spond: spond_fixture,
+            after: fixture,
+        });
+    }
 
-            println!(
-                "Deleting {} removed fixtures for {}",
-                diff.removed.len(),
-                team.name
-            );
-            for spond in diff.removed.iter() {
-                println!("{:?}", spond.to_fixture());
-                spond::delete_spond(&spond.id, &spond_session).await?;
+    for spond in diff.removed {
+        if spond_group.is_some() {
+            let removed = match &team.spond.removal {
+                team::Removal::Delete => spond_client
+                    .delete_spond(&spond.id)
+                    .await
+                    .map(|()| Some(state::Action::Delete)),
+                team::Removal::Cancel { reason } => spond_client
+                    .cancel_spond(&spond.id, reason)
+                    .await
+                    .map(|()| Some(state::Action::Cancel)),
+                team::Removal::Report => Ok(None),
+            };
+            match removed {
+                Ok(Some(action)) => {
+                    let recorded = state.forget(&team.name, &spond.id).and_then(|()| {
+                        journal_change(
+                            action,
+                            &spond.id,
+                            Some(&spond),
+                            spond.to_fixture(venues),
+                            None,
+                        )
+                    });
+                    if let Err(e) = recorded {
+                        report.push_error(
+                            format!("Removed {} but failed to record it: {e}", spond.heading),
+                            e.into(),
+                        );
+                    }
+                }
+                Ok(None) => {}
+                Err(e) => {
+                    report.push_error(format!("Failed to remove {}: {e}", spond.heading), e);
+                    continue;
+                }
             }
         }
+        let deletion = Deletion {
+            fixture: spond.to_fixture(venues),
+            spond,
+            removal: team.spond.removal.clone(),
+        };
+        match deletion.removal {
+            team::Removal::Report => report.reported.push(deletion),
+            team::Removal::Delete | team::Removal::Cancel { .. } => report.deleted.push(deletion),
+        }
     }
-    Ok(())
+
+    Ok(report)
+}
+
+/// Whether a Spond could be for the fixture in a row which couldn't be parsed.
+fn could_be(spond: &spond::Spond, row: &full_time::SkippedRow, records: &state::Records) -> bool {
+    let spond_fixture_id = spond
+        .fixture_key()
+        .and_then(|key| key.fixture_id())
+        .or_else(|| records.get(&spond.id).and_then(|r| r.fixture.id.clone()));
+    match (&row.id, spond_fixture_id) {
+        (Some(row_id), Some(spond_fixture_id)) => *row_id == spond_fixture_id,
+        _ => row.opposition.as_ref().is_some_and(|opposition| {
+            spond
+                .match_info
+                .as_ref()
+                .is_some_and(|m| m.opponent_name.eq_ignore_ascii_case(opposition))
+        }),
+    }
+}
+
+pub async fn sync(
+    team: &Team,
+    venues: &venue::Registry,
+    full_time_client: &full_time::FullTimeClient,
+    spond_client: &spond::SpondClient,
+    state: &state::State,
+    on_edit: OnEdit,
+    sync_type: SyncType,
+) -> Result<SyncReport, Error> {
+    let full_time::UpcomingFixtures {
+        fixtures: full_times_fixtures,
+        skipped,
+    } = full_time_client
+        .get_upcoming_fixtures(team.full_time.season_id, &team.full_time.team, venues)
+        .await?;
+
+    let mut spond_fixtures = spond_client
+        .get_upcoming_matches(&team.spond.group_id, &team.spond.sub_group_id)
+        .await?;
+    spond_fixtures.sort_by_key(|f| f.start_timestamp);
+
+    let upcoming_full_time = full_times_fixtures.len();
+    let upcoming_spond = spond_fixtures.len();
+    let records = state.records(&team.name)?;
+    let mut diff = Diff::new(full_times_fixtures, spond_fixtures, venues, &records);
+    // A fixture whose row couldn't be parsed, e.g. because it's postponed,
+    // looks like it was removed from Full-Time, so Sponds which could be one
+    // of those rows are kept. Every Spond is kept if no row could be parsed,
+    // as when the page's markup changes, or if a row can't be identified.
+    let keep_all = !skipped.is_empty()
+        && (upcoming_full_time == 0
+            || skipped
+                .iter()
+                .any(|row| row.id.is_none() && row.opposition.is_none()));
+    let (kept, removed) = diff
+        .removed
+        .into_iter()
+        .partition(|spond| keep_all || skipped.iter().any(|row| could_be(spond, row, &records)));
+    diff.removed = removed;
+    Ok(SyncReport {
+        upcoming_full_time,
+        upcoming_spond,
+        skipped,
+        kept,
+        ..apply(team, venues, spond_client, state, diff, on_edit, sync_type).await?
+    })
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use chrono::TimeZone;
+
+    mod fixture_change {
+        use super::*;
+
+        #[test]
+        fn lists_each_changed_field() {
+            let before = full_time::Fixture {
+                id: None,
+                typ: full_time::FixtureType::League,
+                side: full_time::FixtureSide::Home,
+                date_time: London.with_ymd_and_hms(2023, 1, 1, 9, 0, 0).unwrap(),
+                opposition: "Opponent".to_owned(),
+                venue: full_time::Venue::Known("Goals".to_owned()),
+            };
+            let after = full_time::Fixture {
+                typ: full_time::FixtureType::Cup,
+                date_time: London.with_ymd_and_hms(2023, 1, 1, 0, 5, 0).unwrap(),
+                venue: full_time::Venue::Known("CintraPark".to_owned()),
+                ..before.clone()
+            };
+            assert_eq!(
+                FixtureChange::between(&before, Some(15), &after),
+                vec![
+                    FixtureChange::KickOff {
+                        before: before.date_time,
+                        after: after.date_time,
+                    },
+                    FixtureChange::Venue {
+                        before: before.venue.clone(),
+                        after: after.venue.clone(),
+                    },
+                    FixtureChange::Competition {
+                        before: full_time::FixtureType::League,
+                        after: full_time::FixtureType::Cup,
+                    },
+                    FixtureChange::Meetup {
+                        before: Some(15),
+                        after: None,
+                    },
+                ]
+            );
+            assert_eq!(FixtureChange::between(&before, Some(15), &before), vec![]);
+        }
+    }
 
     mod fixtures_diff {
         use super::*;
@@ -317,7 +923,12 @@ mod tests {
             fn same_fixture_list_produces_no_diff() {
                 let fixtures = vec![];
                 let sponds = vec![];
-                let diff = Diff::new(fixtures, sponds);
+                let diff = Diff::new(
+                    fixtures,
+                    sponds,
+                    &venue::Registry::default(),
+                    &state::Records::new(),
+                );
                 assert!(
                     diff == Diff {
                         new: vec!(),
@@ -327,49 +938,289 @@ mod tests {
                 )
             }
 
-            // #[test]
-            // fn is_symmetrical() {
-            //     let fixtures = vec![full_time::Fixture {
-            //         typ: full_time::FixtureType::Cup,
-            //         side: full_time::FixtureSide::Away,
-            //         date_time: Utc.with_ymd_and_hms(2023, 1, 1, 10, 5, 0).unwrap(),
-            //         opposition: "Opponent".to_string(),
-            //     }];
-            //     let sponds = vec![];
-            //     let diff = Diff::new(fixtures.clone(), fixtures.clone());
-            //     let reverse_diff = Diff::new(fixtures, fixtures);
-            //     assert!(
-            //         diff == Diff {
-            //             new: reverse_diff.removed,
-            //             modified: reverse_diff.modified,
-            //             removed: reverse_diff.new
-            //         }
-            //     )
-            // }
-
-            // #[test]
-            // fn considers_different_instances_on_the_same_date_as_modifications() {
-            //     let fixture = full_time::Fixture {
-            //         typ: full_time::FixtureType::Cup,
-            //         side: full_time::FixtureSide::Away,
-            //         date_time: Utc.with_ymd_and_hms(2023, 1, 1, 10, 5, 0).unwrap(),
-            //         opposition: "Opponent".to_string(),
-            //     };
-            //     let modified = full_time::Fixture {
-            //         date_time: Utc.with_ymd_and_hms(2023, 1, 1, 10, 10, 0).unwrap(),
-            //         ..fixture.clone()
-            //     };
-            //     let fixtures = vec![fixture];
-            //     let sponds = vec![modified.clone()];
-            //     let diff = Diff::new(fixtures.clone(), sponds.clone());
-            //     assert!(
-            //         diff == Diff {
-            //             new: vec![],
-            //             modified: vec![modified],
-            //             removed: vec![]
-            //         }
-            //     )
-            // }
+            fn fixture(
+                id: Option<&str>,
+                opposition: &str,
+                date_time: DateTime<chrono_tz::Tz>,
+            ) -> full_time::Fixture {
+                full_time::Fixture {
+                    id: id.map(full_time::FixtureId::new),
+                    typ: full_time::FixtureType::League,
+                    side: full_time::FixtureSide::Home,
+                    date_time,
+                    opposition: opposition.to_owned(),
+                    venue: full_time::Venue::Known("Goals".to_owned()),
+                }
+            }
+
+            fn spond(
+                id: &str,
+                fixture: &full_time::Fixture,
+                description: Option<String>,
+            ) -> spond::Spond {
+                let venues = venue::Registry::default();
+                spond::Spond {
+                    id: spond::SpondId::new(id),
+                    creator_id: spond::SpondId::new("creator"),
+                    owners: vec![],
+                    heading: format!("Team - {}", fixture.opposition),
+                    description,
+                    start_timestamp: fixture.to_spond_start_time(),
+                    end_timestamp: fixture.to_spond_end_time(),
+                    meetup_timestamp: None,
+                    meetup_prior: fixture.to_spond_meetup_prior(),
+                    location: Some(spond::Location::from_full_time_venue(
+                        &fixture.venue,
+                        &venues,
+                    )),
+                    match_info: Some(spond::MatchInfo::new(
+                        "Team".to_owned(),
+                        fixture.opposition.clone(),
+                        spond::MatchType::Home,
+                    )),
+                    match_event: true,
+                    created_time: Utc.with_ymd_and_hms(2023, 1, 1, 0, 0, 0).unwrap(),
+                    expired: false,
+                    cancelled: false,
+                }
+            }
+
+            fn keyed_spond(id: &str, fixture: &full_time::Fixture) -> spond::Spond {
+                spond(
+                    id,
+                    fixture,
+                    Some(description_with_fixture_key(None, &fixture.key())),
+                )
+            }
+
+            #[test]
+            fn matches_fixtures_on_the_same_date_by_key() {
+                let first = fixture(
+                    Some("1"),
+                    "Opponent A",
+                    London.with_ymd_and_hms(2023, 1, 1, 9, 0, 0).unwrap(),
+                );
+                let second = fixture(
+                    Some("2"),
+                    "Opponent B",
+                    London.with_ymd_and_hms(2023, 1, 1, 11, 0, 0).unwrap(),
+                );
+                let sponds = vec![keyed_spond("b", &second), keyed_spond("a", &first)];
+                let diff = Diff::new(
+                    vec![first, second],
+                    sponds,
+                    &venue::Registry::default(),
+                    &state::Records::new(),
+                );
+                assert_eq!(
+                    diff,
+                    Diff {
+                        new: vec![],
+                        modified: vec![],
+                        removed: vec![]
+                    }
+                )
+            }
+
+            #[test]
+            fn matches_rescheduled_fixture_by_key() {
+                let original = fixture(
+                    Some("1"),
+                    "Opponent A",
+                    London.with_ymd_and_hms(2023, 1, 1, 9, 0, 0).unwrap(),
+                );
+                let other = fixture(
+                    Some("2"),
+                    "Opponent B",
+                    London.with_ymd_and_hms(2023, 1, 8, 9, 0, 0).unwrap(),
+                );
+                let rescheduled = full_time::Fixture {
+                    date_time: London.with_ymd_and_hms(2023, 1, 8, 11, 0, 0).unwrap(),
+                    ..original.clone()
+                };
+                let original_spond = keyed_spond("a", &original);
+                let other_spond = keyed_spond("b", &other);
+                let diff = Diff::new(
+                    vec![rescheduled.clone(), other],
+                    vec![original_spond.clone(), other_spond],
+                    &venue::Registry::default(),
+                    &state::Records::new(),
+                );
+                assert_eq!(
+                    diff,
+                    Diff {
+                        new: vec![],
+                        modified: vec![(rescheduled, original_spond)],
+                        removed: vec![]
+                    }
+                )
+            }
+
+            #[test]
+            fn matches_fixture_which_gained_an_id_by_opposition() {
+                let without_id = fixture(
+                    None,
+                    "Opponent A",
+                    London.with_ymd_and_hms(2023, 1, 1, 9, 0, 0).unwrap(),
+                );
+                let with_id = full_time::Fixture {
+                    id: Some(full_time::FixtureId::new("1")),
+                    ..without_id.clone()
+                };
+                let spond = keyed_spond("a", &without_id);
+                let diff = Diff::new(
+                    vec![with_id.clone()],
+                    vec![spond.clone()],
+                    &venue::Registry::default(),
+                    &state::Records::new(),
+                );
+                assert_eq!(
+                    diff,
+                    Diff {
+                        new: vec![],
+                        modified: vec![(with_id, spond)],
+                        removed: vec![]
+                    }
+                )
+            }
+
+            #[test]
+            fn matches_fixture_which_lost_its_id_by_opposition() {
+                let with_id = fixture(
+                    Some("1"),
+                    "Opponent A",
+                    London.with_ymd_and_hms(2023, 1, 1, 9, 0, 0).unwrap(),
+                );
+                let without_id = full_time::Fixture {
+                    id: None,
+                    date_time: London.with_ymd_and_hms(2023, 1, 8, 9, 0, 0).unwrap(),
+                    ..with_id.clone()
+                };
+                let spond = keyed_spond("a", &with_id);
+                let diff = Diff::new(
+                    vec![without_id.clone()],
+                    vec![spond.clone()],
+                    &venue::Registry::default(),
+                    &state::Records::new(),
+                );
+                assert_eq!(
+                    diff,
+                    Diff {
+                        new: vec![],
+                        modified: vec![(without_id, spond)],
+                        removed: vec![]
+                    }
+                )
+            }
+
+            #[test]
+            fn restores_a_cleared_location() {
+                let fixture = fixture(
+                    Some("1"),
+                    "Opponent A",
+                    London.with_ymd_and_hms(2023, 1, 1, 9, 0, 0).unwrap(),
+                );
+                let spond = spond::Spond {
+                    location: None,
+                    ..keyed_spond("a", &fixture)
+                };
+                let diff = Diff::new(
+                    vec![fixture.clone()],
+                    vec![spond.clone()],
+                    &venue::Registry::default(),
+                    &state::Records::new(),
+                );
+                assert_eq!(
+                    diff,
+                    Diff {
+                        new: vec![],
+                        modified: vec![(fixture, spond)],
+                        removed: vec![]
+                    }
+                )
+            }
+
+            #[test]
+            fn falls_back_to_date_for_legacy_sponds() {
+                let fixture = fixture(
+                    Some("1"),
+                    "Opponent A",
+                    London.with_ymd_and_hms(2023, 1, 1, 9, 0, 0).unwrap(),
+                );
+                let legacy = spond("a", &fixture, None);
+                let diff = Diff::new(
+                    vec![fixture.clone()],
+                    vec![legacy.clone()],
+                    &venue::Registry::default(),
+                    &state::Records::new(),
+                );
+                assert_eq!(
+                    diff,
+                    Diff {
+                        new: vec![],
+                        modified: vec![(fixture, legacy)],
+                        removed: vec![]
+                    }
+                )
+            }
+
+            #[test]
+            fn matches_rescheduled_fixture_by_recorded_fixture() {
+                let original = fixture(
+                    Some("1"),
+                    "Opponent A",
+                    London.with_ymd_and_hms(2023, 1, 1, 9, 0, 0).unwrap(),
+                );
+                let rescheduled = full_time::Fixture {
+                    date_time: London.with_ymd_and_hms(2023, 1, 8, 9, 0, 0).unwrap(),
+                    ..original.clone()
+                };
+                let unkeyed = spond("a", &original, None);
+                let state = state::State::open_in_memory().unwrap();
+                state.record("Team", &unkeyed, &original, &unkeyed).unwrap();
+                let diff = Diff::new(
+                    vec![rescheduled.clone()],
+                    vec![unkeyed.clone()],
+                    &venue::Registry::default(),
+                    &state.records("Team").unwrap(),
+                );
+                assert_eq!(
+                    diff,
+                    Diff {
+                        new: vec![],
+                        modified: vec![(rescheduled, unkeyed)],
+                        removed: vec![]
+                    }
+                )
+            }
+
+            #[test]
+            fn considers_different_instances_on_the_same_date_as_modifications() {
+                let fixture = fixture(
+                    None,
+                    "Opponent",
+                    London.with_ymd_and_hms(2023, 1, 1, 10, 5, 0).unwrap(),
+                );
+                let modified = full_time::Fixture {
+                    date_time: London.with_ymd_and_hms(2023, 1, 1, 10, 10, 0).unwrap(),
+                    ..fixture.clone()
+                };
+                let existing = spond("a", &fixture, None);
+                let diff = Diff::new(
+                    vec![modified.clone()],
+                    vec![existing.clone()],
+                    &venue::Registry::default(),
+                    &state::Records::new(),
+                );
+                assert_eq!(
+                    diff,
+                    Diff {
+                        new: vec![],
+                        modified: vec![(modified, existing)],
+                        removed: vec![]
+                    }
+                )
+            }
         }
     }
 }
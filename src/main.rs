@@ -1,27 +1,254 @@
-use std::collections::HashMap;
+use std::{
+    io::{BufRead, IsTerminal, Write},
+    path::PathBuf,
+    time::Duration,
+};
 
-use clap::{Parser, Subcommand};
+use clap::{Args as ClapArgs, Parser, Subcommand, ValueEnum};
 
-use full_time_spond_sync::{spond, sync, team, SyncType};
+use full_time_spond_sync::{
+    apply, full_time, http,
+    plan::{self, Plan, TeamPlan},
+    spond, state, sync, team,
+    undo::{undo, UndoReport},
+    Deletion, Diff, Error, OnEdit, SyncReport, SyncType, Team, Update,
+};
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+enum Output {
+    /// A table of changes per team for humans
+    Table,
+    /// A JSON array with a report per team, see `SyncReport` for the schema
+    Json,
+}
+
+#[derive(ClapArgs, Debug)]
+struct OutputArgs {
+    /// How to print the changes
+    #[arg(short, long, value_enum, default_value_t = Output::Table)]
+    output: Output,
+}
+
+#[derive(ClapArgs, Debug)]
+struct ConfirmArgs {
+    /// Show each change before making it and ask whether to make it, skip it
+    /// or abort the rest of the run. This is the default when stdin is a terminal
+    #[arg(short, long, conflicts_with = "yes")]
+    interactive: bool,
+
+    /// Make every change without asking, for unattended runs
+    #[arg(short, long, conflicts_with = "interactive")]
+    yes: bool,
+}
+
+impl ConfirmArgs {
+    fn should_ask(&self) -> Result<bool, Box<dyn std::error::Error>> {
+        if self.yes {
+            Ok(false)
+        } else if std::io::stdin().is_terminal() {
+            Ok(true)
+        } else if self.interactive {
+            Err("--interactive needs a terminal, pass --yes for unattended runs.".into())
+        } else {
+            Ok(false)
+        }
+    }
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+enum EditPolicy {
+    /// Leave the Spond as it is and report the edits
+    Skip,
+    /// Overwrite the edits and report them
+    Warn,
+    /// Fail the team's sync unless --force is given
+    Refuse,
+}
+
+#[derive(ClapArgs, Debug)]
+struct EditArgs {
+    /// What to do with Sponds whose Full-Time fields have been edited in
+    /// Spond since they were last synced
+    #[arg(long, value_enum, default_value_t = EditPolicy::Skip)]
+    on_edit: EditPolicy,
+
+    /// Overwrite Sponds which have been edited in Spond
+    #[arg(long)]
+    force: bool,
+}
+
+impl EditArgs {
+    fn on_edit(&self) -> OnEdit {
+        match (self.force, self.on_edit) {
+            (true, _) => OnEdit::Overwrite,
+            (false, EditPolicy::Skip) => OnEdit::Skip,
+            (false, EditPolicy::Warn) => OnEdit::Warn,
+            (false, EditPolicy::Refuse) => OnEdit::Refuse,
+        }
+    }
+}
+
+#[derive(ClapArgs, Debug)]
+struct SyncArgs {
+    #[command(flatten)]
+    output: OutputArgs,
+
+    #[command(flatten)]
+    edit: EditArgs,
+
+    #[command(flatten)]
+    confirm: ConfirmArgs,
+}
+
+#[derive(ClapArgs, Debug)]
+struct DiffArgs {
+    #[command(flatten)]
+    output: OutputArgs,
+
+    #[command(flatten)]
+    edit: EditArgs,
+
+    /// Save the changes to a plan file which `apply` can execute later
+    #[arg(long)]
+    save_plan: Option<PathBuf>,
+}
+
+#[derive(ClapArgs, Debug)]
+struct ApplyArgs {
+    /// A plan file saved by `diff --save-plan`
+    plan: PathBuf,
+
+    #[command(flatten)]
+    output: OutputArgs,
+
+    #[command(flatten)]
+    edit: EditArgs,
+
+    #[command(flatten)]
+    confirm: ConfirmArgs,
+}
+
+#[derive(ClapArgs, Debug)]
+struct HistoryArgs {
+    /// Only list the changes for a fixture, given by its Full-Time fixture id
+    /// or the key in its Spond's description
+    #[arg(long)]
+    fixture: Option<String>,
+
+    #[command(flatten)]
+    output: OutputArgs,
+}
+
+#[derive(ClapArgs, Debug)]
+struct UndoArgs {
+    /// The run to undo, as printed at the end of a sync or apply
+    run_id: i64,
+
+    #[command(flatten)]
+    output: OutputArgs,
+}
 
 #[derive(Subcommand, Debug)]
 enum SubCommand {
     /// Diff the fixtures and sync the changes with Spond
-    Sync,
+    Sync(SyncArgs),
     /// Diff the fixtures and print the changes without syncing to Spond
-    Diff,
+    Diff(DiffArgs),
+    /// Sync exactly the changes in a saved plan, as long as none of the
+    /// Sponds it changes have been changed since
+    Apply(ApplyArgs),
+    /// List the changes sync has made to Spond, for the --teams given or all teams
+    History(HistoryArgs),
+    /// Revert the changes a sync or apply made to Spond
+    Undo(UndoArgs),
+}
+
+#[derive(ClapArgs, Debug)]
+struct HttpArgs {
+    /// How many times to send a request which times out or fails with a 429 or
+    /// 5xx status before giving up
+    #[arg(long, default_value_t = http::RetryPolicy::default().attempts)]
+    attempts: u32,
+
+    /// The delay in milliseconds before retrying a failed request, which
+    /// doubles for each retry unless the response has a Retry-After header
+    #[arg(long, default_value_t = http::RetryPolicy::default().initial_backoff.as_millis() as u64)]
+    backoff_ms: u64,
+
+    /// The fraction of each retry's delay which is randomised, from 0 to 1
+    #[arg(long, default_value_t = http::RetryPolicy::default().jitter)]
+    jitter: f64,
+
+    /// The seconds to wait for a response before the request is retried
+    #[arg(long, default_value_t = http::DEFAULT_TIMEOUT.as_secs())]
+    timeout_secs: u64,
+
+    /// The most requests to send to Spond per second, 0 for no limit
+    #[arg(long, default_value_t = 5.0)]
+    spond_rate_limit: f64,
+
+    /// The most requests to send to Full-Time per second, 0 for no limit
+    #[arg(long, default_value_t = 1.0)]
+    full_time_rate_limit: f64,
+}
+
+impl HttpArgs {
+    fn client(&self) -> reqwest::Client {
+        http::client(Duration::from_secs(self.timeout_secs))
+    }
+
+    fn sender(&self, requests_per_second: f64) -> http::Sender {
+        http::Sender::new(
+            http::RetryPolicy {
+                attempts: self.attempts.max(1),
+                initial_backoff: Duration::from_millis(self.backoff_ms),
+                jitter: self.jitter,
+                ..http::RetryPolicy::default()
+            },
+            http::RateLimit::per_second(requests_per_second),
+        )
+    }
 }
 
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
 struct Args {
-    /// An admin's Spond username
+    /// An admin's Spond username, defaults to $SPOND_EMAIL
+    #[arg(short, long)]
+    email: Option<String>,
+
+    /// An admin's Spond password, defaults to $SPOND_PASSWORD. Prefer the
+    /// environment, a credentials file or the prompt to keep it out of shell history
     #[arg(short, long)]
-    email: String,
+    password: Option<String>,
 
-    /// An admin's Spond password
+    /// A JSON file with the admin's Spond `email` and `password`, only readable
+    /// by its owner. Defaults to $SPOND_CREDENTIALS_FILE or credentials.json in
+    /// the config directory
+    #[arg(long)]
+    credentials_file: Option<PathBuf>,
+
+    /// The config file to load, defaults to $FULL_TIME_SPOND_SYNC_CONFIG or
+    /// teams.{json,toml,yaml} in $XDG_CONFIG_HOME/full_time_spond_sync
     #[arg(short, long)]
-    password: String,
+    config: Option<PathBuf>,
+
+    /// The base URL of the Spond API
+    #[arg(long, default_value = spond::DEFAULT_BASE_URL)]
+    spond_url: String,
+
+    /// The base URL of Full-Time, or a file:// URL of a directory of saved
+    /// displayTeam.html pages named <team id>.html
+    #[arg(long, default_value = full_time::DEFAULT_BASE_URL)]
+    full_time_url: String,
+
+    /// The directory to keep what was last synced in, defaults to
+    /// $XDG_STATE_HOME/full_time_spond_sync
+    #[arg(long)]
+    state_dir: Option<PathBuf>,
+
+    #[command(flatten)]
+    http: HttpArgs,
 
     // The teams to run for
     #[arg(long, value_delimiter = ',')]
@@ -31,38 +258,667 @@ struct Args {
     cmd: SubCommand,
 }
 
+/// Resolves the Spond credentials from the command line, then the environment,
+/// then a credentials file and finally by prompting for whatever is missing.
+fn resolve_credentials(
+    email: Option<String>,
+    password: Option<String>,
+    credentials_file: Option<PathBuf>,
+) -> std::io::Result<spond::UserCredentials> {
+    let email = email.or_else(|| std::env::var(spond::UserCredentials::EMAIL_ENV_VAR).ok());
+    let password =
+        password.or_else(|| std::env::var(spond::UserCredentials::PASSWORD_ENV_VAR).ok());
+    let file_credentials = if email.is_some() && password.is_some() {
+        None
+    } else {
+        credentials_file
+            .or_else(|| std::env::var_os(spond::UserCredentials::FILE_ENV_VAR).map(PathBuf::from))
+            .or_else(|| {
+                team::default_config_dir()
+                    .map(|dir| dir.join("credentials.json"))
+                    .filter(|f| f.is_file())
+            })
+            .map(|file| spond::UserCredentials::from_file(&file))
+            .transpose()?
+    };
+    let (email, password) = with_file_credentials(email, password, file_credentials);
+    spond::UserCredentials::prompt(email, password)
+}
+
+/// Fills in whichever of the email and password weren't given from the
+/// credentials file, if there is one.
+fn with_file_credentials(
+    email: Option<String>,
+    password: Option<String>,
+    file_credentials: Option<spond::UserCredentials>,
+) -> (Option<String>, Option<String>) {
+    match file_credentials {
+        Some(file) => (
+            Some(email.unwrap_or(file.email)),
+            Some(password.unwrap_or(file.password)),
+        ),
+        None => (email, password),
+    }
+}
+
+fn fixture_row(action: &str, fixture: &full_time::Fixture) -> String {
+    format!(
+        "{:<8} {:<18} {:<6} {:<4} {:<32} {}",
+        action,
+        fixture.date_time.format("%a %d/%m/%y %H:%M"),
+        match fixture.typ {
+            full_time::FixtureType::Cup => "Cup",
+            full_time::FixtureType::League => "League",
+        },
+        match fixture.side {
+            full_time::FixtureSide::Home => "Home",
+            full_time::FixtureSide::Away => "Away",
+        },
+        fixture.opposition,
+        fixture.venue,
+    )
+}
+
+fn update_rows(update: &Update) -> String {
+    let mut rows = fixture_row("update", &update.after);
+    for change in update.changes.iter() {
+        rows.push_str(&format!("\n{:<8} - {}", "", change));
+    }
+    rows
+}
+
+fn deletion_row(deletion: &Deletion) -> String {
+    let action = match deletion.removal {
+        team::Removal::Delete => "delete",
+        team::Removal::Cancel { .. } => "cancel",
+        team::Removal::Report => "removed",
+    };
+    match &deletion.fixture {
+        Some(fixture) => fixture_row(action, fixture),
+        None => format!("{:<8} {}", action, deletion.spond.heading),
+    }
+}
+
+fn render_table(report: &SyncReport) {
+    println!(
+        "{}{}: {} upcoming on Full-Time, {} upcoming on Spond",
+        report.team,
+        match report.sync_type {
+            SyncType::Dry => " (dry run)",
+            SyncType::Real => "",
+        },
+        report.upcoming_full_time,
+        report.upcoming_spond,
+    );
+    println!(
+        "{:<8} {:<18} {:<6} {:<4} {:<32} VENUE",
+        "ACTION", "KICK-OFF", "TYPE", "SIDE", "OPPOSITION"
+    );
+    for fixture in report.created.iter() {
+        println!("{}", fixture_row("create", fixture));
+    }
+    for update in report.updated.iter() {
+        println!("{}", update_rows(update));
+    }
+    for deletion in report.deleted.iter().chain(report.reported.iter()) {
+        println!("{}", deletion_row(deletion));
+    }
+    for edited in report.edited.iter() {
+        println!(
+            "{:<8} {}, {}",
+            "edited",
+            edited,
+            if edited.overwritten {
+                "overwriting them"
+            } else {
+                "leaving it"
+            }
+        );
+    }
+    for skipped_row in report.skipped.iter() {
+        println!("{:<8} {}", "skipped", skipped_row);
+    }
+    for spond in report.kept.iter() {
+        println!(
+            "{:<8} {}, not on Full-Time but rows were skipped",
+            "kept", spond.heading
+        );
+    }
+    for error in report.errors.iter() {
+        println!("{:<8} {}", "error", error);
+    }
+    println!();
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Answer {
+    Accept,
+    Skip,
+    Abort,
+}
+
+/// Asks on stderr whether to make a change, so that it doesn't mix with
+/// `--output json`, and reads the answer from `input`.
+fn ask(input: &mut impl BufRead, change: &str) -> std::io::Result<Answer> {
+    loop {
+        eprint!("{change}\nMake this change? [y]es, [n]o or [a]bort: ");
+        std::io::stderr().flush()?;
+        let mut line = String::new();
+        if input.read_line(&mut line)? == 0 {
+            return Ok(Answer::Abort);
+        }
+        match line.trim().to_lowercase().as_str() {
+            "y" | "yes" => return Ok(Answer::Accept),
+            "n" | "no" => return Ok(Answer::Skip),
+            "a" | "abort" => return Ok(Answer::Abort),
+            _ => continue,
+        }
+    }
+}
+
+/// Asks about each change in a dry run's report, returning the accepted
+/// changes or `None` if the operator aborted.
+fn confirm_changes(
+    input: &mut impl BufRead,
+    dry_run: &SyncReport,
+) -> std::io::Result<Option<Diff>> {
+    eprintln!("{}:", dry_run.team);
+    let mut diff = Diff {
+        new: vec![],
+        modified: vec![],
+        removed: vec![],
+    };
+    for fixture in dry_run.created.iter() {
+        match ask(input, &fixture_row("create", fixture))? {
+            Answer::Accept => diff.new.push(fixture.clone()),
+            Answer::Skip => {}
+            Answer::Abort => return Ok(None),
+        }
+    }
+    for update in dry_run.updated.iter() {
+        match ask(input, &update_rows(update))? {
+            Answer::Accept => diff
+                .modified
+                .push((update.after.clone(), update.spond.clone())),
+            Answer::Skip => {}
+            Answer::Abort => return Ok(None),
+        }
+    }
+    // Reporting a removed fixture changes nothing in Spond, so there's nothing to ask.
+    diff.removed
+        .extend(dry_run.reported.iter().map(|d| d.spond.clone()));
+    for deletion in dry_run.deleted.iter() {
+        match ask(input, &deletion_row(deletion))? {
+            Answer::Accept => diff.removed.push(deletion.spond.clone()),
+            Answer::Skip => {}
+            Answer::Abort => return Ok(None),
+        }
+    }
+    Ok(Some(diff))
+}
+
+fn show_history(
+    state: &state::State,
+    teams: &[String],
+    args: HistoryArgs,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let fixture_key = args.fixture.map(|fixture| {
+        if fixture.contains(':') {
+            full_time::FixtureKey::new(fixture)
+        } else {
+            full_time::Fixture::key_for_id(&full_time::FixtureId::new(fixture))
+        }
+    });
+    let changes = if teams.is_empty() {
+        state.history(None, fixture_key.as_ref())?
+    } else {
+        let mut changes = vec![];
+        for team in teams {
+            changes.extend(state.history(Some(team), fixture_key.as_ref())?);
+        }
+        changes
+    };
+
+    match args.output.output {
+        Output::Table => {
+            for change in changes.iter() {
+                println!(
+                    "{} {} Spond {} by {}",
+                    change.time.format("%a %d/%m/%y %H:%M"),
+                    change.team,
+                    change.spond_id,
+                    change.operator
+                );
+                if let Some(fixture) = change.after.as_ref().or(change.before.as_ref()) {
+                    println!("{}", fixture_row(change.action.as_str(), fixture));
+                }
+                for fixture_change in change.changes() {
+                    println!("{:<8} - {}", "", fixture_change);
+                }
+            }
+        }
+        Output::Json => println!("{}", serde_json::to_string_pretty(&changes)?),
+    }
+    Ok(())
+}
+
+fn render_undo(report: &UndoReport) {
+    println!("Run {}:", report.run_id);
+    for entry in report.reverted.iter() {
+        let spond = entry.spond.as_ref().map_or("", |s| s.heading.as_str());
+        println!(
+            "{:<8} {:<8} {} Spond {} {}",
+            "undone",
+            entry.action.as_str(),
+            entry.team,
+            entry.spond_id,
+            spond
+        );
+    }
+    for error in report.errors.iter() {
+        println!("{:<8} {}", "error", error);
+    }
+}
+
+/// The report for a team whose sync failed before it could make any changes.
+fn failed_report(team: &str, sync_type: SyncType, error: Box<dyn std::error::Error>) -> SyncReport {
+    let mut report = SyncReport::new(team, sync_type);
+    match error.downcast::<Error>() {
+        Ok(error) => {
+            let message = match *error {
+                Error::Edited(_) => format!("{error}, pass --force to overwrite them."),
+                _ => error.to_string(),
+            };
+            report.push_error(message, *error)
+        }
+        Err(error) => report.errors.push(error.to_string()),
+    }
+    report
+}
+
+/// What's needed to sync the teams of a club.
+struct Runner {
+    club: team::Club,
+    full_time_client: full_time::FullTimeClient,
+    spond_client: spond::SpondClient,
+    state: state::State,
+    on_edit: OnEdit,
+}
+
+impl Runner {
+    fn team(&self, name: &str) -> Option<&Team> {
+        self.club
+            .teams
+            .iter()
+            .find(|t| t.name.to_lowercase() == name.to_lowercase())
+    }
+
+    async fn sync(&self, team: &Team, sync_type: SyncType) -> Result<SyncReport, Error> {
+        sync(
+            team,
+            &self.club.venues,
+            &self.full_time_client,
+            &self.spond_client,
+            &self.state,
+            self.on_edit,
+            sync_type,
+        )
+        .await
+    }
+
+    async fn apply(
+        &self,
+        team: &Team,
+        diff: Diff,
+        sync_type: SyncType,
+    ) -> Result<SyncReport, Error> {
+        apply(
+            team,
+            &self.club.venues,
+            &self.spond_client,
+            &self.state,
+            diff,
+            self.on_edit,
+            sync_type,
+        )
+        .await
+    }
+
+    /// Applies the changes from a dry run that the operator accepts, returning
+    /// `None` if they aborted.
+    async fn apply_confirmed(
+        &self,
+        team: &Team,
+        dry_run: SyncReport,
+    ) -> Result<Option<SyncReport>, Box<dyn std::error::Error>> {
+        let Some(diff) = confirm_changes(&mut std::io::stdin().lock(), &dry_run)? else {
+            return Ok(None);
+        };
+        let mut report = self.apply(team, diff, SyncType::Real).await?;
+        // Sponds whose edits are left alone aren't in the diff, so apply can't
+        // report them.
+        report
+            .edited
+            .extend(dry_run.edited.into_iter().filter(|e| !e.overwritten));
+        Ok(Some(SyncReport {
+            upcoming_full_time: dry_run.upcoming_full_time,
+            upcoming_spond: dry_run.upcoming_spond,
+            skipped: dry_run.skipped,
+            kept: dry_run.kept,
+            ..report
+        }))
+    }
+
+    /// Syncs the named teams, returning the reports and whether the operator aborted.
+    async fn sync_teams(
+        &self,
+        team_names: Vec<String>,
+        sync_type: SyncType,
+        should_ask: bool,
+    ) -> (Vec<SyncReport>, bool) {
+        let mut reports = vec![];
+        for team_name in team_names {
+            let Some(team) = self.team(&team_name).cloned() else {
+                eprintln!("Unknown team name: {}", team_name);
+                continue;
+            };
+            let report = if should_ask {
+                match self.sync(&team, SyncType::Dry).await {
+                    Ok(dry_run) => self.apply_confirmed(&team, dry_run).await,
+                    Err(e) => Err(e.into()),
+                }
+            } else {
+                self.sync(&team, sync_type)
+                    .await
+                    .map(Some)
+                    .map_err(Into::into)
+            };
+            match report {
+                Ok(Some(report)) => reports.push(report),
+                Ok(None) => return (reports, true),
+                Err(e) => reports.push(failed_report(&team.name, sync_type, e)),
+            }
+        }
+        (reports, false)
+    }
+
+    /// Checks every team in the plan before applying any of it, so that a stale
+    /// plan is refused as a whole. Returns the reports and whether the operator aborted.
+    async fn apply_plan(
+        &self,
+        plan: Plan,
+        should_ask: bool,
+    ) -> Result<(Vec<SyncReport>, bool), Box<dyn std::error::Error>> {
+        let mut teams = vec![];
+        let mut conflicts = vec![];
+        for team_plan in plan.teams {
+            let team = self
+                .team(&team_plan.team)
+                .cloned()
+                .ok_or_else(|| format!("Unknown team name in plan: {}", team_plan.team))?;
+            for conflict in plan::verify(&team_plan, &team, &self.spond_client).await? {
+                conflicts.push(format!("{}: {}", team.name, conflict));
+            }
+            teams.push((team, team_plan.diff));
+        }
+        if !conflicts.is_empty() {
+            for conflict in conflicts.iter() {
+                eprintln!("{conflict}");
+            }
+            return Err(format!(
+                "The plan from {} is out of date, run diff again.",
+                plan.created_time.format("%d/%m/%y %H:%M")
+            )
+            .into());
+        }
+
+        let mut reports = vec![];
+        for (team, diff) in teams {
+            let report = if should_ask {
+                match self.apply(&team, diff, SyncType::Dry).await {
+                    Ok(dry_run) => self.apply_confirmed(&team, dry_run).await,
+                    Err(e) => Err(e.into()),
+                }
+            } else {
+                self.apply(&team, diff, SyncType::Real)
+                    .await
+                    .map(Some)
+                    .map_err(Into::into)
+            };
+            match report {
+                Ok(Some(report)) => reports.push(report),
+                Ok(None) => return Ok((reports, true)),
+                Err(e) => reports.push(failed_report(&team.name, SyncType::Real, e)),
+            }
+        }
+        Ok((reports, false))
+    }
+}
+
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let args = Args::parse();
-    let creds = spond::UserCredentials {
-        email: args.email,
-        password: args.password,
+    let state_dir = args
+        .state_dir
+        .or_else(state::default_state_dir)
+        .ok_or("Unable to determine the default state directory.")?;
+    std::fs::create_dir_all(&state_dir)?;
+    let state = state::State::open(&state_dir.join(state::STATE_FILE))?;
+    if let SubCommand::History(h) = args.cmd {
+        return show_history(&state, &args.teams, h);
+    }
+
+    let creds = resolve_credentials(args.email, args.password, args.credentials_file)?;
+    let mut runner = Runner {
+        club: team::load(args.config.as_deref())?,
+        full_time_client: full_time::FullTimeClient::with_http_client(
+            args.http.client(),
+            args.full_time_url,
+        )
+        .with_sender(args.http.sender(args.http.full_time_rate_limit)),
+        spond_client: spond::SpondClient::with_http_client(args.http.client(), args.spond_url)
+            .with_sender(args.http.sender(args.http.spond_rate_limit)),
+        state,
+        on_edit: OnEdit::default(),
     };
-    let teams = team::load()?;
-    let team_lookup = teams
-        .into_iter()
-        .map(|t| (t.name.to_lowercase(), t))
-        .collect::<HashMap<_, _>>();
-
-    for team_name in args.teams {
-        let team = team_lookup.get(&team_name.to_lowercase());
-        match team {
-            Some(team) => {
-                sync(
-                    team,
-                    &creds,
-                    spond::GroupId::new("12BC6CAB8503463C8845B14A6CBC8D4A"),
-                    match args.cmd {
-                        SubCommand::Diff => SyncType::Dry,
-                        SubCommand::Sync => SyncType::Real,
-                    },
+    match runner.spond_client.login(&creds).await {
+        Err(Error::SpondAuth(_)) => {
+            return Err(format!(
+                "Spond didn't accept the password for {}, check it or update the credentials.",
+                creds.email
+            )
+            .into())
+        }
+        result => result?,
+    }
+
+    let (reports, output, aborted) = match args.cmd {
+        SubCommand::Diff(d) => {
+            runner.on_edit = d.edit.on_edit();
+            let (reports, _) = runner.sync_teams(args.teams, SyncType::Dry, false).await;
+            if let Some(path) = d.save_plan {
+                Plan::new(
+                    reports
+                        .iter()
+                        .filter(|r| r.errors.is_empty())
+                        .map(TeamPlan::from_report)
+                        .collect(),
                 )
-                .await?
+                .save(&path)?;
+            }
+            (reports, d.output.output, false)
+        }
+        SubCommand::Sync(s) => {
+            runner.on_edit = s.edit.on_edit();
+            let (reports, aborted) = runner
+                .sync_teams(args.teams, SyncType::Real, s.confirm.should_ask()?)
+                .await;
+            (reports, s.output.output, aborted)
+        }
+        SubCommand::Apply(a) => {
+            runner.on_edit = a.edit.on_edit();
+            let plan = Plan::load(&a.plan)?;
+            let (reports, aborted) = runner.apply_plan(plan, a.confirm.should_ask()?).await?;
+            (reports, a.output.output, aborted)
+        }
+        SubCommand::Undo(u) => {
+            let report = undo(
+                state::RunId::new(u.run_id),
+                &runner.club.teams,
+                &runner.club.venues,
+                &runner.spond_client,
+                &runner.state,
+            )
+            .await?;
+            match u.output.output {
+                Output::Table => render_undo(&report),
+                Output::Json => println!("{}", serde_json::to_string_pretty(&report)?),
             }
-            None => {
-                println!("Unknown team name: {}", team_name);
+            if !report.errors.is_empty() {
+                return Err(format!(
+                    "Failed to undo {} change(s), run undo again to retry them.",
+                    report.errors.len()
+                )
+                .into());
             }
+            return Ok(());
         }
+        SubCommand::History(_) => unreachable!("history is shown before logging in"),
+    };
+
+    if let Some(run_id) = runner.state.run_id() {
+        eprintln!("The changes were made in run {run_id}, revert them with `undo {run_id}`.");
+    }
+    finish(reports, output, aborted)
+}
+
+fn finish(
+    reports: Vec<SyncReport>,
+    output: Output,
+    aborted: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    for report in reports.iter() {
+        for error in report.spond_errors.iter() {
+            eprintln!("{}: Spond error: {}", report.team, error);
+        }
+    }
+    match output {
+        Output::Table => reports.iter().for_each(render_table),
+        Output::Json => println!("{}", serde_json::to_string_pretty(&reports)?),
+    }
+
+    let failures = reports
+        .iter()
+        .flat_map(|r| r.errors.iter().map(move |e| format!("{}: {e}", r.team)))
+        .collect::<Vec<_>>();
+    if aborted {
+        Err("Aborted, the remaining changes were not made.".into())
+    } else if !failures.is_empty() {
+        Err(format!(
+            "{} change(s) failed, sync again to retry them:\n{}",
+            failures.len(),
+            failures.join("\n")
+        )
+        .into())
+    } else {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn answer(input: &str) -> Answer {
+        ask(&mut input.as_bytes(), "create Jedis v Sith").unwrap()
+    }
+
+    #[test]
+    fn reads_each_answer() {
+        assert_eq!(answer("y\n"), Answer::Accept);
+        assert_eq!(answer("Yes\n"), Answer::Accept);
+        assert_eq!(answer("n\n"), Answer::Skip);
+        assert_eq!(answer("no\n"), Answer::Skip);
+        assert_eq!(answer("a\n"), Answer::Abort);
+        assert_eq!(answer("abort\n"), Answer::Abort);
+    }
+
+    #[test]
+    fn asks_again_until_the_answer_is_recognised() {
+        assert_eq!(answer("maybe\n\nn\n"), Answer::Skip);
+    }
+
+    #[test]
+    fn aborts_at_the_end_of_input() {
+        assert_eq!(answer(""), Answer::Abort);
+        assert_eq!(answer("maybe\n"), Answer::Abort);
+    }
+
+    fn file_credentials() -> Option<spond::UserCredentials> {
+        Some(spond::UserCredentials {
+            email: "file@example.com".to_owned(),
+            password: "file password".to_owned(),
+        })
+    }
+
+    fn some(s: &str) -> Option<String> {
+        Some(s.to_owned())
+    }
+
+    #[test]
+    fn keeps_given_credentials_over_the_file() {
+        assert_eq!(
+            with_file_credentials(
+                some("coach@example.com"),
+                some("secret"),
+                file_credentials()
+            ),
+            (some("coach@example.com"), some("secret"))
+        );
+    }
+
+    #[test]
+    fn takes_only_missing_credentials_from_the_file() {
+        assert_eq!(
+            with_file_credentials(None, some("secret"), file_credentials()),
+            (some("file@example.com"), some("secret"))
+        );
+        assert_eq!(
+            with_file_credentials(some("coach@example.com"), None, file_credentials()),
+            (some("coach@example.com"), some("file password"))
+        );
+    }
+
+    #[test]
+    fn leaves_credentials_to_prompt_for_without_a_file() {
+        assert_eq!(
+            with_file_credentials(None, some("secret"), None),
+            (None, some("secret"))
+        );
+        let credentials =
+            spond::UserCredentials::prompt(some("coach@example.com"), some("secret")).unwrap();
+        assert_eq!(
+            (credentials.email, credentials.password),
+            ("coach@example.com".to_owned(), "secret".to_owned())
+        );
+    }
+
+    #[test]
+    fn interactive_and_yes_conflict() {
+        assert_eq!(
+            Args::try_parse_from(["full-time-spond-sync", "sync", "-i", "-y"])
+                .unwrap_err()
+                .kind(),
+            clap::error::ErrorKind::ArgumentConflict
+        );
+    }
+
+    #[test]
+    fn yes_never_asks() {
+        let confirm = ConfirmArgs {
+            interactive: false,
+            yes: true,
+        };
+        assert!(!confirm.should_ask().unwrap());
     }
-    Ok(())
 }
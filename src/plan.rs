@@ -0,0 +1,130 @@
+use std::path::Path;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{full_time, spond, Diff, Error, SyncReport, Team};
+
+/// The changes `diff --save-plan` computed for each team, to be executed
+/// later by `apply`. The modified and removed Sponds are stored as they were
+/// when the plan was made so that `apply` can tell if they've since changed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Plan {
+    pub created_time: DateTime<Utc>,
+    pub teams: Vec<TeamPlan>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TeamPlan {
+    pub team: String,
+    pub diff: Diff,
+}
+
+impl TeamPlan {
+    /// The plan for the changes a dry run reported, which are the changes in
+    /// the diff it was given.
+    pub fn from_report(report: &SyncReport) -> Self {
+        Self {
+            team: report.team.clone(),
+            diff: Diff {
+                new: report.created.clone(),
+                modified: report
+                    .updated
+                    .iter()
+                    .map(|u| (u.after.clone(), u.spond.clone()))
+                    .collect(),
+                removed: report
+                    .deleted
+                    .iter()
+                    .chain(report.reported.iter())
+                    .map(|d| d.spond.clone())
+                    .collect(),
+            },
+        }
+    }
+}
+
+impl Plan {
+    pub fn new(teams: Vec<TeamPlan>) -> Self {
+        Self {
+            created_time: Utc::now(),
+            teams,
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        std::fs::write(path, serde_json::to_string_pretty(self)?)
+    }
+
+    pub fn load(path: &Path) -> std::io::Result<Self> {
+        Ok(serde_json::from_str(&std::fs::read_to_string(path)?)?)
+    }
+}
+
+/// A reason a team's plan can no longer be applied as it was made.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Conflict {
+    /// The Spond has been edited since the plan was made.
+    Changed(spond::SpondId),
+    /// The Spond has been deleted or is no longer upcoming.
+    Missing(spond::SpondId),
+    /// A Spond has been created for a fixture the plan would create.
+    AlreadyCreated {
+        key: full_time::FixtureKey,
+        spond: spond::SpondId,
+    },
+}
+
+impl std::fmt::Display for Conflict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Conflict::Changed(id) => write!(f, "Spond {id} has changed"),
+            Conflict::Missing(id) => write!(f, "Spond {id} no longer exists"),
+            Conflict::AlreadyCreated { key, spond } => {
+                write!(f, "Spond {spond} has been created for {}", &**key)
+            }
+        }
+    }
+}
+
+/// Checks the team's current Sponds against the versions the plan was based on.
+pub async fn verify(
+    team_plan: &TeamPlan,
+    team: &Team,
+    spond_client: &spond::SpondClient,
+) -> Result<Vec<Conflict>, Error> {
+    let current = spond_client
+        .get_upcoming_matches(&team.spond.group_id, &team.spond.sub_group_id)
+        .await?;
+
+    let planned = team_plan
+        .diff
+        .modified
+        .iter()
+        .map(|(_, spond)| spond)
+        .chain(team_plan.diff.removed.iter());
+    let mut conflicts = planned
+        .filter_map(
+            |planned| match current.iter().find(|s| s.id == planned.id) {
+                None => Some(Conflict::Missing(planned.id.clone())),
+                Some(current) if current != planned => Some(Conflict::Changed(planned.id.clone())),
+                Some(_) => None,
+            },
+        )
+        .collect::<Vec<_>>();
+
+    for fixture in team_plan.diff.new.iter() {
+        let key = fixture.key();
+        if let Some(spond) = current
+            .iter()
+            .find(|s| s.fixture_key().as_ref() == Some(&key))
+        {
+            conflicts.push(Conflict::AlreadyCreated {
+                key,
+                spond: spond.id.clone(),
+            });
+        }
+    }
+
+    Ok(conflicts)
+}
@@ -0,0 +1,75 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::spond;
+
+/// A field of a Spond which sync sets from Full-Time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Field {
+    KickOff,
+    End,
+    Meetup,
+    Location,
+    Opposition,
+    MatchType,
+}
+
+impl std::fmt::Display for Field {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Field::KickOff => write!(f, "kick-off"),
+            Field::End => write!(f, "end"),
+            Field::Meetup => write!(f, "meetup"),
+            Field::Location => write!(f, "location"),
+            Field::Opposition => write!(f, "opposition"),
+            Field::MatchType => write!(f, "match type"),
+        }
+    }
+}
+
+/// The values sync last wrote to a Spond's Full-Time fields.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Snapshot {
+    start_timestamp: DateTime<Utc>,
+    end_timestamp: DateTime<Utc>,
+    meetup_prior: Option<u16>,
+    location: Option<spond::Location>,
+    opponent_name: Option<String>,
+    match_type: Option<spond::MatchType>,
+}
+
+impl Snapshot {
+    pub fn of(spond: &spond::Spond) -> Self {
+        Self {
+            start_timestamp: spond.start_timestamp,
+            end_timestamp: spond.end_timestamp,
+            meetup_prior: spond.meetup_prior,
+            location: spond.location.clone(),
+            opponent_name: spond.match_info.as_ref().map(|m| m.opponent_name.clone()),
+            match_type: spond.match_info.as_ref().map(|m| m.typ.clone()),
+        }
+    }
+
+    /// The fields which have been changed in Spond since this snapshot was taken.
+    pub fn edited_fields(&self, spond: &spond::Spond) -> Vec<Field> {
+        let current = Self::of(spond);
+        [
+            (
+                Field::KickOff,
+                self.start_timestamp != current.start_timestamp,
+            ),
+            (Field::End, self.end_timestamp != current.end_timestamp),
+            (Field::Meetup, self.meetup_prior != current.meetup_prior),
+            (Field::Location, self.location != current.location),
+            (
+                Field::Opposition,
+                self.opponent_name != current.opponent_name,
+            ),
+            (Field::MatchType, self.match_type != current.match_type),
+        ]
+        .into_iter()
+        .filter_map(|(field, edited)| edited.then_some(field))
+        .collect()
+    }
+}
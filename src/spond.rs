@@ -1,36 +1,93 @@
 use chrono::{DateTime, NaiveDate, Utc};
+use reqwest::StatusCode;
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 
-use crate::full_time;
+use crate::{error::SpondApiError, full_time, http, venue, Error};
 
-#[derive(Debug, Serialize)]
+#[derive(Serialize, Deserialize)]
 pub struct UserCredentials {
     pub email: String,
     pub password: String,
 }
 
-#[derive(Debug, Deserialize)]
+impl std::fmt::Debug for UserCredentials {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("UserCredentials")
+            .field("email", &self.email)
+            .field("password", &"<redacted>")
+            .finish()
+    }
+}
+
+impl UserCredentials {
+    pub const EMAIL_ENV_VAR: &'static str = "SPOND_EMAIL";
+    pub const PASSWORD_ENV_VAR: &'static str = "SPOND_PASSWORD";
+    pub const FILE_ENV_VAR: &'static str = "SPOND_CREDENTIALS_FILE";
+
+    /// Reads the credentials from a JSON file with `email` and `password` fields.
+    /// On unix the file must not be accessible by the group or other users.
+    pub fn from_file(path: &std::path::Path) -> std::io::Result<Self> {
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(path)?.permissions().mode();
+            if mode & 0o077 != 0 {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::PermissionDenied,
+                    format!(
+                        "Credentials file '{}' has mode {:o}, it must only be accessible by its owner (e.g. 600).",
+                        path.display(),
+                        mode & 0o777
+                    ),
+                ));
+            }
+        }
+        serde_json::from_str(&std::fs::read_to_string(path)?).map_err(|e| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("Invalid credentials file '{}': {e}", path.display()),
+            )
+        })
+    }
+
+    /// Prompts for whichever of the email and password weren't given. The
+    /// password isn't echoed when stdin is a terminal.
+    pub fn prompt(email: Option<String>, password: Option<String>) -> std::io::Result<Self> {
+        use std::io::{BufRead, IsTerminal, Write};
+
+        fn read_line(prompt: &str) -> std::io::Result<String> {
+            eprint!("{prompt}");
+            std::io::stderr().flush()?;
+            let mut line = String::new();
+            std::io::stdin().lock().read_line(&mut line)?;
+            Ok(line.trim_end_matches(['\r', '\n']).to_owned())
+        }
+
+        let email = match email {
+            Some(email) => email,
+            None => read_line("Spond email: ")?,
+        };
+        let password = match password {
+            Some(password) => password,
+            None if std::io::stdin().is_terminal() => {
+                rpassword::prompt_password("Spond password: ")?
+            }
+            None => read_line("Spond password: ")?,
+        };
+        Ok(Self { email, password })
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
 pub struct UserSession {
     #[serde(rename = "loginToken")]
     login_token: String,
     #[serde(rename = "passwordToken")]
+    #[allow(dead_code)]
     password_token: String,
 }
 
-pub async fn login(credentials: &UserCredentials) -> reqwest::Result<UserSession> {
-    let response = reqwest::Client::new()
-        .post("https://api.spond.com/core/v1/login")
-        .json(credentials)
-        .send()
-        .await?;
-
-    match response.error_for_status() {
-        Ok(res) => res.json().await,
-        Err(e) => Err(e),
-    }
-}
-
 #[derive(Debug)]
 pub enum Order {
     Asc,
@@ -153,6 +210,24 @@ impl SubGroupId {
     }
 }
 
+/// Sent with a create so that Spond ignores a retry of a create it has
+/// already made.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IdempotencyKey(String);
+
+impl IdempotencyKey {
+    /// The key for creating the Spond for `subject` in a sub group, hex encoded
+    /// so that it's always a valid header value.
+    pub fn new(sub_group_id: &SubGroupId, subject: &str) -> Self {
+        Self(
+            format!("{}/{subject}", sub_group_id.0)
+                .bytes()
+                .map(|b| format!("{b:02x}"))
+                .collect(),
+        )
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
 pub struct SubGroup {
     #[serde(rename = "id")]
@@ -192,21 +267,6 @@ pub struct Group {
     pub roles: Vec<Role>,
 }
 
-pub async fn get_group(group_id: &GroupId, session: &UserSession) -> reqwest::Result<Group> {
-    let response = reqwest::Client::new()
-        .get(format!(
-            "https://api.spond.com/core/v1/group/{}",
-            group_id.0
-        ))
-        .bearer_auth(session.login_token.clone())
-        .send()
-        .await?;
-    match response.error_for_status() {
-        Ok(res) => res.json().await,
-        Err(e) => Err(e),
-    }
-}
-
 #[derive(Debug)]
 struct GetSpondsRequest {
     add_profile_info: bool,
@@ -230,9 +290,21 @@ pub enum SpondType {
     Event,
 }
 
-#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq, Hash)]
 pub struct SpondId(String);
 
+impl SpondId {
+    pub fn new(s: impl Into<String>) -> Self {
+        Self(s.into())
+    }
+}
+
+impl std::fmt::Display for SpondId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum Response {
@@ -261,97 +333,49 @@ pub struct Location {
     feature: String,
     #[serde(rename = "address")]
     address: String,
-    #[serde(rename = "latitude")]
-    latitude: Decimal,
-    #[serde(rename = "longitude")]
-    longitude: Decimal,
+    #[serde(rename = "latitude", skip_serializing_if = "Option::is_none")]
+    latitude: Option<Decimal>,
+    #[serde(rename = "longitude", skip_serializing_if = "Option::is_none")]
+    longitude: Option<Decimal>,
 }
 
 impl Location {
-    const GOALS_NAME: &str = "Goals Reading";
-    const KINGS_ACADEMY_NAME: &str = "King’s Academy Prospect";
-    const WOODFORD_PARK_3G_NAME: &str = "Woodford Park - 3G";
-    const WOODFORD_PARK_GRASS_NAME: &str = "Woodford Park - Grass";
-    const CINTRA_PARK_NAME: &str = "Cintra Park";
-    const NORTH_STREET_THEALE: &str = "North Street Theale";
-
-    pub fn to_full_time_venue(&self) -> full_time::Venue {
-        match self.feature.as_str() {
-            Self::GOALS_NAME => full_time::Venue::Goals,
-            Self::KINGS_ACADEMY_NAME => full_time::Venue::KingsAcademy,
-            Self::WOODFORD_PARK_3G_NAME => full_time::Venue::WoodfordPark3G,
-            Self::WOODFORD_PARK_GRASS_NAME => full_time::Venue::WoodfordParkGrass,
-            Self::CINTRA_PARK_NAME => full_time::Venue::CintraPark,
-            Self::NORTH_STREET_THEALE => full_time::Venue::NorthStreetTheale,
-            _ => panic!("Unknown location '{}'", self.feature),
-        }
-    }
-
-    pub fn goals() -> Self {
-        Self {
-            feature: Location::GOALS_NAME.to_owned(),
-            address: "Woodlands Avenue, Woodley, Reading".to_owned(),
-            latitude: rust_decimal_macros::dec!(51.453648),
-            longitude: rust_decimal_macros::dec!(-0.9185121),
-        }
-    }
-
-    pub fn kings_academy() -> Self {
-        Self {
-            feature: Location::KINGS_ACADEMY_NAME.to_owned(),
-            address: "King's Academy Prospect, Cockney Hill, Tilehurst, Reading RG30 4EX"
-                .to_owned(),
-            latitude: rust_decimal_macros::dec!(51.44725212792346),
-            longitude: rust_decimal_macros::dec!(-1.01962455537632),
-        }
-    }
-
-    pub fn woodford_park_3g() -> Self {
-        Self {
-            feature: Location::WOODFORD_PARK_3G_NAME.to_owned(),
-            address: "Woodford Park Leisure Centre, Haddon Dr, Woodley, Reading, RG5 4LY"
-                .to_owned(),
-            latitude: rust_decimal_macros::dec!(51.457008),
-            longitude: rust_decimal_macros::dec!(-0.9058048),
-        }
-    }
-
-    pub fn woodford_park_grass() -> Self {
-        Self {
-            feature: Location::WOODFORD_PARK_GRASS_NAME.to_owned(),
-            address: "Woodford Park Leisure Centre, Haddon Dr, Woodley, Reading, RG5 4LY"
-                .to_owned(),
-            latitude: rust_decimal_macros::dec!(51.457008),
-            longitude: rust_decimal_macros::dec!(-0.9058048),
+    pub fn to_full_time_venue(&self, venues: &venue::Registry) -> full_time::Venue {
+        match venues.find_by_feature(&self.feature) {
+            Some(known) => full_time::Venue::Known(known.name.clone()),
+            None => full_time::Venue::Other {
+                name: self.feature.clone(),
+                address: self.address.clone(),
+            },
         }
     }
 
-    pub fn cintra_park() -> Self {
+    /// A location without coordinates for grounds we only know by name and address.
+    pub fn other(name: &str, address: &str) -> Self {
         Self {
-            feature: Location::CINTRA_PARK_NAME.to_owned(),
-            address: "Cintra Park, Reading, RG2 7ES".to_owned(),
-            latitude: rust_decimal_macros::dec!(51.442006),
-            longitude: rust_decimal_macros::dec!(-0.958364),
+            feature: name.to_owned(),
+            address: address.to_owned(),
+            latitude: None,
+            longitude: None,
         }
     }
 
-    pub fn north_street_theale() -> Self {
+    pub fn from_venue_definition(definition: &venue::Definition) -> Self {
         Self {
-            feature: Location::NORTH_STREET_THEALE.to_owned(),
-            address: "Englefield Rd, Theale, Reading RG7 5AS".to_owned(),
-            latitude: rust_decimal_macros::dec!(51.438580113890666),
-            longitude: rust_decimal_macros::dec!(-1.0837532493059963),
+            feature: definition.feature.clone(),
+            address: definition.address.clone(),
+            latitude: Some(definition.latitude),
+            longitude: Some(definition.longitude),
         }
     }
 
-    pub fn from_full_time_venue(venue: full_time::Venue) -> Self {
+    pub fn from_full_time_venue(venue: &full_time::Venue, venues: &venue::Registry) -> Self {
         match venue {
-            full_time::Venue::Goals => Self::goals(),
-            full_time::Venue::KingsAcademy => Self::kings_academy(),
-            full_time::Venue::WoodfordPark3G => Self::woodford_park_3g(),
-            full_time::Venue::WoodfordParkGrass => Self::woodford_park_grass(),
-            full_time::Venue::CintraPark => Self::cintra_park(),
-            full_time::Venue::NorthStreetTheale => Self::north_street_theale(),
+            full_time::Venue::Known(name) => venues
+                .get(name)
+                .map(Self::from_venue_definition)
+                .unwrap_or_else(|| Self::other(name, "")),
+            full_time::Venue::Other { name, address } => Self::other(name, address),
         }
     }
 }
@@ -438,48 +462,8 @@ pub struct Spond {
     pub created_time: DateTime<Utc>,
     #[serde(rename = "expired")]
     pub expired: bool,
-}
-
-async fn get_sponds(
-    request: GetSpondsRequest,
-    session: &UserSession,
-) -> reqwest::Result<Vec<Spond>> {
-    let response = reqwest::Client::new()
-        .get("https://api.spond.com/core/v1/sponds")
-        .query(
-            &vec![
-                Some(("addProfileInfo", request.add_profile_info.to_string())),
-                Some((
-                    "excludeAvailability",
-                    request.exclude_availability.to_string(),
-                )),
-                Some(("excludeRepeating", request.exclude_repeating.to_string())),
-                request.group_id.map(|id| ("groupId", id.0)),
-                Some(("includeComments", request.include_comments.to_string())),
-                Some(("includeHidden", request.include_hidden.to_string())),
-                Some(("match", request.mtch.to_string())),
-                request.max.map(|max| ("max", max.to_string())),
-                request.order.map(|order| ("order", order.into())),
-                request
-                    .min_start_timestamp
-                    .map(|dt| ("minStartTimestamp", dt.to_rfc3339())),
-                request
-                    .max_start_timestamp
-                    .map(|dt| ("maxStartTimestamp", dt.to_rfc3339())),
-                Some(("scheduled", request.scheduled.to_string())),
-                request.sub_group_id.map(|id| ("subGroupId", id.0)),
-            ]
-            .iter()
-            .filter_map(|x| x.clone())
-            .collect::<Vec<_>>(),
-        )
-        .bearer_auth(session.login_token.clone())
-        .send()
-        .await?;
-    match response.error_for_status() {
-        Ok(res) => res.json().await,
-        Err(e) => Err(e),
-    }
+    #[serde(rename = "cancelled", default)]
+    pub cancelled: bool,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize)]
@@ -523,6 +507,8 @@ pub struct Recipients {
 pub struct CreateSpondRequest {
     #[serde(rename = "heading")]
     pub heading: String,
+    #[serde(rename = "description")]
+    pub description: Option<String>,
     #[serde(rename = "spondType")]
     pub spond_type: SpondType,
     #[serde(rename = "startTimestamp")]
@@ -561,58 +547,242 @@ pub struct CreateSpondRequest {
     pub recipients: Recipients,
 }
 
-pub async fn create_spond(
-    request: CreateSpondRequest,
-    session: &UserSession,
-) -> reqwest::Result<()> {
-    let response = reqwest::Client::new()
-        .post("https://api.spond.com/core/v1/sponds")
-        .json(&request)
-        .bearer_auth(session.login_token.clone())
-        .send()
-        .await?;
-    match response.error_for_status() {
-        Ok(_) => Ok(()),
-        Err(e) => Err(e),
+pub const DEFAULT_BASE_URL: &str = "https://api.spond.com/core/v1";
+
+#[derive(Debug, Clone, Serialize)]
+struct CancelSpondRequest<'a> {
+    #[serde(rename = "quiet")]
+    quiet: bool,
+    #[serde(rename = "cancellationMessage")]
+    cancellation_message: &'a str,
+}
+
+/// Turns an error status into an [`Error`] which keeps why Spond rejected the
+/// request, treating 401 and 403 as the session being rejected.
+async fn error_for_status(
+    endpoint: String,
+    response: reqwest::Response,
+) -> Result<reqwest::Response, Error> {
+    if response.status().is_success() {
+        return Ok(response);
+    }
+    let error = SpondApiError::from_response(endpoint, response).await;
+    match error.status {
+        StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => Err(Error::SpondAuth(error)),
+        _ => Err(Error::SpondApi(error)),
     }
 }
 
-pub async fn update_spond(spond: Spond, session: &UserSession) -> reqwest::Result<()> {
-    let response = reqwest::Client::new()
-        .post(format!(
-            "https://api.spond.com/core/v1/sponds/{}",
-            spond.id.0
-        ))
-        .json(&spond)
-        .bearer_auth(session.login_token.clone())
-        .send()
-        .await?;
-    match response.error_for_status() {
-        Ok(_) => Ok(()),
-        Err(e) => Err(e),
+/// Describes a request as its method and path, e.g. `POST /sponds`.
+fn endpoint(request: &reqwest::RequestBuilder) -> String {
+    request
+        .try_clone()
+        .and_then(|request| request.build().ok())
+        .map(|request| format!("{} {}", request.method(), request.url().path()))
+        .unwrap_or_default()
+}
+
+/// A client for the Spond API which shares its connection pool and session
+/// between requests.
+#[derive(Debug, Clone)]
+pub struct SpondClient {
+    http: reqwest::Client,
+    base_url: String,
+    session: Option<UserSession>,
+    email: Option<String>,
+    sender: http::Sender,
+}
+
+impl Default for SpondClient {
+    fn default() -> Self {
+        Self::new(DEFAULT_BASE_URL)
     }
 }
 
-pub async fn delete_spond(id: &SpondId, session: &UserSession) -> reqwest::Result<()> {
-    let response = reqwest::Client::new()
-        .delete(format!("https://api.spond.com/core/v1/sponds/{}", id.0))
-        .query(&[("quiet", "true")])
-        .bearer_auth(session.login_token.clone())
-        .send()
+impl SpondClient {
+    pub fn new(base_url: impl Into<String>) -> Self {
+        Self::with_http_client(http::client(http::DEFAULT_TIMEOUT), base_url)
+    }
+
+    pub fn with_http_client(http: reqwest::Client, base_url: impl Into<String>) -> Self {
+        Self {
+            http,
+            base_url: base_url.into().trim_end_matches('/').to_owned(),
+            session: None,
+            email: None,
+            sender: http::Sender::default(),
+        }
+    }
+
+    pub fn with_sender(self, sender: http::Sender) -> Self {
+        Self { sender, ..self }
+    }
+
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    /// The email of the user who logged in, if anyone has.
+    pub fn email(&self) -> Option<&str> {
+        self.email.as_deref()
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}/{}", self.base_url, path)
+    }
+
+    fn authorized(&self, request: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+        match &self.session {
+            Some(session) => request.bearer_auth(&session.login_token),
+            None => request,
+        }
+    }
+
+    /// Sends a request with the session, retrying and rate limiting it.
+    async fn send(&self, request: reqwest::RequestBuilder) -> Result<reqwest::Response, Error> {
+        self.send_with(&self.sender, request).await
+    }
+
+    async fn send_with(
+        &self,
+        sender: &http::Sender,
+        request: reqwest::RequestBuilder,
+    ) -> Result<reqwest::Response, Error> {
+        let endpoint = endpoint(&request);
+        let response = sender
+            .send(self.authorized(request))
+            .await
+            .map_err(Error::SpondRequest)?;
+        error_for_status(endpoint, response).await
+    }
+
+    /// Logs in and uses the resulting session for all subsequent requests.
+    pub async fn login(&mut self, credentials: &UserCredentials) -> Result<(), Error> {
+        let request = self.http.post(self.url("login")).json(credentials);
+        let endpoint = endpoint(&request);
+        let response = self
+            .sender
+            .send(request)
+            .await
+            .map_err(Error::SpondRequest)?;
+
+        match error_for_status(endpoint, response).await {
+            Ok(res) => {
+                self.session = Some(res.json().await.map_err(Error::SpondRequest)?);
+                self.email = Some(credentials.email.clone());
+                Ok(())
+            }
+            // Spond rejects a wrong password with a 400 rather than a 401.
+            Err(Error::SpondApi(e)) if e.status == StatusCode::BAD_REQUEST => {
+                Err(Error::SpondAuth(e))
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    pub async fn get_group(&self, group_id: &GroupId) -> Result<Group, Error> {
+        let response = self
+            .send(self.http.get(self.url(&format!("group/{}", group_id.0))))
+            .await?;
+        response.json().await.map_err(Error::SpondRequest)
+    }
+
+    async fn get_sponds(&self, request: GetSpondsRequest) -> Result<Vec<Spond>, Error> {
+        let response = self
+            .send(
+                self.http.get(self.url("sponds")).query(
+                    &vec![
+                        Some(("addProfileInfo", request.add_profile_info.to_string())),
+                        Some((
+                            "excludeAvailability",
+                            request.exclude_availability.to_string(),
+                        )),
+                        Some(("excludeRepeating", request.exclude_repeating.to_string())),
+                        request.group_id.map(|id| ("groupId", id.0)),
+                        Some(("includeComments", request.include_comments.to_string())),
+                        Some(("includeHidden", request.include_hidden.to_string())),
+                        Some(("match", request.mtch.to_string())),
+                        request.max.map(|max| ("max", max.to_string())),
+                        request.order.map(|order| ("order", order.into())),
+                        request
+                            .min_start_timestamp
+                            .map(|dt| ("minStartTimestamp", dt.to_rfc3339())),
+                        request
+                            .max_start_timestamp
+                            .map(|dt| ("maxStartTimestamp", dt.to_rfc3339())),
+                        Some(("scheduled", request.scheduled.to_string())),
+                        request.sub_group_id.map(|id| ("subGroupId", id.0)),
+                    ]
+                    .iter()
+                    .filter_map(|x| x.clone())
+                    .collect::<Vec<_>>(),
+                ),
+            )
+            .await?;
+        response.json().await.map_err(Error::SpondRequest)
+    }
+
+    /// Creates a Spond, returning it as Spond stored it. Creates with the same
+    /// idempotency key only create one Spond.
+    pub async fn create_spond(
+        &self,
+        request: CreateSpondRequest,
+        idempotency_key: &IdempotencyKey,
+    ) -> Result<Spond, Error> {
+        let response = self
+            .send(
+                self.http
+                    .post(self.url("sponds"))
+                    .header("Idempotency-Key", &idempotency_key.0)
+                    .json(&request),
+            )
+            .await?;
+        response.json().await.map_err(Error::SpondRequest)
+    }
+
+    pub async fn update_spond(&self, spond: Spond) -> Result<(), Error> {
+        self.send(
+            self.http
+                .post(self.url(&format!("sponds/{}", spond.id.0)))
+                .json(&spond),
+        )
         .await?;
-    match response.error_for_status() {
-        Ok(_) => Ok(()),
-        Err(e) => Err(e),
+        Ok(())
+    }
+
+    pub async fn delete_spond(&self, id: &SpondId) -> Result<(), Error> {
+        self.send(
+            self.http
+                .delete(self.url(&format!("sponds/{}", id.0)))
+                .query(&[("quiet", "true")]),
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Cancels a Spond, keeping the invitees' responses, and sends them `reason`.
+    pub async fn cancel_spond(&self, id: &SpondId, reason: &str) -> Result<(), Error> {
+        // A cancel which failed may still have messaged the invitees, so it
+        // isn't retried in case they're messaged twice.
+        self.send_with(
+            &self.sender.with_retry(http::RetryPolicy::none()),
+            self.http
+                .post(self.url(&format!("sponds/{}/cancel", id.0)))
+                .json(&CancelSpondRequest {
+                    quiet: false,
+                    cancellation_message: reason,
+                }),
+        )
+        .await?;
+        Ok(())
     }
-}
 
-pub async fn get_upcoming_matches(
-    group_id: &GroupId,
-    sub_group_id: &SubGroupId,
-    session: &UserSession,
-) -> Result<Vec<Spond>, String> {
-    get_sponds(
-        GetSpondsRequest {
+    pub async fn get_upcoming_matches(
+        &self,
+        group_id: &GroupId,
+        sub_group_id: &SubGroupId,
+    ) -> Result<Vec<Spond>, Error> {
+        self.get_sponds(GetSpondsRequest {
             add_profile_info: false,
             exclude_availability: true,
             exclude_repeating: true,
@@ -626,9 +796,33 @@ pub async fn get_upcoming_matches(
             max: Some(100),
             order: Some(Order::Asc),
             sub_group_id: Some(sub_group_id.clone()),
-        },
-        &session,
-    )
-    .await
-    .map_err(|e| e.to_string())
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[test]
+    fn refuses_a_credentials_file_others_can_read() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let path = std::env::temp_dir().join(format!("credentials-{}.json", std::process::id()));
+        std::fs::write(
+            &path,
+            r#"{"email": "coach@example.com", "password": "secret"}"#,
+        )
+        .unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o644)).unwrap();
+        let readable = UserCredentials::from_file(&path).map_err(|e| e.kind());
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600)).unwrap();
+        let private = UserCredentials::from_file(&path).map(|c| c.email);
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(readable.unwrap_err(), std::io::ErrorKind::PermissionDenied);
+        assert_eq!(private.unwrap(), "coach@example.com");
+    }
 }
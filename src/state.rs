@@ -0,0 +1,414 @@
+use std::{
+    cell::Cell,
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use chrono::{DateTime, Utc};
+use rusqlite::{params, types::Type, Connection};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::{full_time, snapshot::Snapshot, spond, FixtureChange};
+
+/// The name of the SQLite database in the state directory.
+pub const STATE_FILE: &str = "state.sqlite3";
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS sponds (
+    team TEXT NOT NULL,
+    spond_id TEXT NOT NULL,
+    fixture TEXT NOT NULL,
+    request TEXT NOT NULL,
+    snapshot TEXT NOT NULL,
+    created_time TEXT NOT NULL,
+    updated_time TEXT NOT NULL,
+    PRIMARY KEY (team, spond_id)
+);
+CREATE TABLE IF NOT EXISTS changes (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    time TEXT NOT NULL,
+    operator TEXT NOT NULL,
+    team TEXT NOT NULL,
+    spond_id TEXT NOT NULL,
+    fixture_key TEXT NOT NULL,
+    action TEXT NOT NULL,
+    before TEXT,
+    after TEXT
+);
+CREATE TABLE IF NOT EXISTS runs (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    started_time TEXT NOT NULL,
+    operator TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS journal (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    run_id INTEGER NOT NULL REFERENCES runs (id),
+    team TEXT NOT NULL,
+    spond_id TEXT NOT NULL,
+    action TEXT NOT NULL,
+    spond TEXT,
+    undone_time TEXT
+);
+";
+
+pub fn default_state_dir() -> Option<PathBuf> {
+    std::env::var_os("XDG_STATE_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| {
+            std::env::var_os("HOME").map(|home| Path::new(&home).join(".local").join("state"))
+        })
+        .map(|dir| dir.join("full_time_spond_sync"))
+}
+
+/// What sync last wrote to one of a team's Sponds.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Record {
+    /// The Full-Time fixture the Spond was last synced with.
+    pub fixture: full_time::Fixture,
+    /// The body of the last create or update request sent for the Spond.
+    pub request: serde_json::Value,
+    /// The Spond's Full-Time fields as they were last written.
+    pub snapshot: Snapshot,
+    pub created_time: DateTime<Utc>,
+    pub updated_time: DateTime<Utc>,
+}
+
+/// A team's records keyed by Spond id.
+pub type Records = HashMap<spond::SpondId, Record>;
+
+/// What sync did to a Spond.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Action {
+    Create,
+    Update,
+    Delete,
+    Cancel,
+}
+
+impl Action {
+    const ALL: [Action; 4] = [
+        Action::Create,
+        Action::Update,
+        Action::Delete,
+        Action::Cancel,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Action::Create => "create",
+            Action::Update => "update",
+            Action::Delete => "delete",
+            Action::Cancel => "cancel",
+        }
+    }
+}
+
+/// An entry in the audit log of every change sync has made to Spond.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Change {
+    pub time: DateTime<Utc>,
+    /// The email of the Spond user sync was run as.
+    pub operator: String,
+    pub team: String,
+    pub spond_id: spond::SpondId,
+    pub action: Action,
+    /// The fixture the Spond was for before the change, if it was for one.
+    pub before: Option<full_time::Fixture>,
+    /// The fixture the Spond was for after the change, unless it was removed.
+    pub after: Option<full_time::Fixture>,
+}
+
+impl Change {
+    /// The key of the fixture the change was for.
+    pub fn fixture_key(&self) -> Option<full_time::FixtureKey> {
+        self.after
+            .as_ref()
+            .or(self.before.as_ref())
+            .map(|f| f.key())
+    }
+
+    /// The fields an update changed.
+    pub fn changes(&self) -> Vec<FixtureChange> {
+        match (&self.before, &self.after) {
+            (Some(before), Some(after)) => {
+                FixtureChange::between(before, before.to_spond_meetup_prior(), after)
+            }
+            _ => vec![],
+        }
+    }
+}
+
+/// Identifies the changes made to Spond by one invocation of the tool.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct RunId(i64);
+
+impl RunId {
+    pub fn new(id: i64) -> Self {
+        Self(id)
+    }
+}
+
+impl std::fmt::Display for RunId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// A change made during a run, with what's needed to undo it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct JournalEntry {
+    pub id: i64,
+    pub team: String,
+    pub spond_id: spond::SpondId,
+    pub action: Action,
+    /// The Spond as it was before the change, or `None` if it was created.
+    pub spond: Option<spond::Spond>,
+    pub undone: bool,
+}
+
+fn to_json(value: &impl Serialize) -> rusqlite::Result<String> {
+    serde_json::to_string(value).map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))
+}
+
+fn timestamp(row: &rusqlite::Row, idx: usize) -> rusqlite::Result<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(&row.get::<_, String>(idx)?)
+        .map(|t| t.with_timezone(&Utc))
+        .map_err(|e| rusqlite::Error::FromSqlConversionFailure(idx, Type::Text, Box::new(e)))
+}
+
+fn action(row: &rusqlite::Row, idx: usize) -> rusqlite::Result<Action> {
+    let action = row.get::<_, String>(idx)?;
+    Action::ALL
+        .into_iter()
+        .find(|a| a.as_str() == action)
+        .ok_or_else(|| {
+            rusqlite::Error::FromSqlConversionFailure(
+                idx,
+                Type::Text,
+                format!("unknown action '{action}'").into(),
+            )
+        })
+}
+
+fn from_json<T: DeserializeOwned>(row: &rusqlite::Row, idx: usize) -> rusqlite::Result<T> {
+    serde_json::from_str(&row.get::<_, String>(idx)?)
+        .map_err(|e| rusqlite::Error::FromSqlConversionFailure(idx, Type::Text, Box::new(e)))
+}
+
+fn optional_json<T: DeserializeOwned>(
+    row: &rusqlite::Row,
+    idx: usize,
+) -> rusqlite::Result<Option<T>> {
+    row.get::<_, Option<String>>(idx)?
+        .map(|json| {
+            serde_json::from_str(&json).map_err(|e| {
+                rusqlite::Error::FromSqlConversionFailure(idx, Type::Text, Box::new(e))
+            })
+        })
+        .transpose()
+}
+
+/// The local record of what sync has written to Spond, kept between runs in
+/// a SQLite database.
+#[derive(Debug)]
+pub struct State {
+    conn: Connection,
+    /// The run changes are journaled under, started by the first change.
+    run_id: Cell<Option<RunId>>,
+}
+
+impl State {
+    pub fn open(path: &Path) -> rusqlite::Result<Self> {
+        Self::init(Connection::open(path)?)
+    }
+
+    pub fn open_in_memory() -> rusqlite::Result<Self> {
+        Self::init(Connection::open_in_memory()?)
+    }
+
+    fn init(conn: Connection) -> rusqlite::Result<Self> {
+        conn.execute_batch(SCHEMA)?;
+        Ok(Self {
+            conn,
+            run_id: Cell::new(None),
+        })
+    }
+
+    pub fn records(&self, team: &str) -> rusqlite::Result<Records> {
+        let mut statement = self.conn.prepare(
+            "SELECT spond_id, fixture, request, snapshot, created_time, updated_time
+             FROM sponds WHERE team = ?1",
+        )?;
+        let records = statement.query_map([team], |row| {
+            Ok((
+                spond::SpondId::new(row.get::<_, String>(0)?),
+                Record {
+                    fixture: from_json(row, 1)?,
+                    request: from_json(row, 2)?,
+                    snapshot: from_json(row, 3)?,
+                    created_time: timestamp(row, 4)?,
+                    updated_time: timestamp(row, 5)?,
+                },
+            ))
+        })?;
+        records.collect()
+    }
+
+    /// Records that `request` was sent to sync `spond` with `fixture`.
+    pub fn record(
+        &self,
+        team: &str,
+        spond: &spond::Spond,
+        fixture: &full_time::Fixture,
+        request: &impl Serialize,
+    ) -> rusqlite::Result<()> {
+        let now = Utc::now().to_rfc3339();
+        self.conn.execute(
+            "INSERT INTO sponds (team, spond_id, fixture, request, snapshot, created_time, updated_time)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?6)
+             ON CONFLICT (team, spond_id) DO UPDATE SET
+                 fixture = excluded.fixture,
+                 request = excluded.request,
+                 snapshot = excluded.snapshot,
+                 updated_time = excluded.updated_time",
+            params![
+                team,
+                spond.id.to_string(),
+                to_json(fixture)?,
+                to_json(request)?,
+                to_json(&Snapshot::of(spond))?,
+                now,
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// The run this state has journaled changes under, if it has made any.
+    pub fn run_id(&self) -> Option<RunId> {
+        self.run_id.get()
+    }
+
+    fn current_run(&self, operator: &str) -> rusqlite::Result<RunId> {
+        if let Some(run_id) = self.run_id.get() {
+            return Ok(run_id);
+        }
+        self.conn.execute(
+            "INSERT INTO runs (started_time, operator) VALUES (?1, ?2)",
+            params![Utc::now().to_rfc3339(), operator],
+        )?;
+        let run_id = RunId(self.conn.last_insert_rowid());
+        self.run_id.set(Some(run_id));
+        Ok(run_id)
+    }
+
+    /// Appends a change to the audit log and to the journal of the current
+    /// run, along with the Spond as it was before the change so that the run
+    /// can be undone.
+    pub fn journal_change(
+        &self,
+        change: &Change,
+        spond_before: Option<&spond::Spond>,
+    ) -> rusqlite::Result<()> {
+        let run_id = self.current_run(&change.operator)?;
+        self.conn.execute(
+            "INSERT INTO journal (run_id, team, spond_id, action, spond)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                run_id.0,
+                change.team,
+                change.spond_id.to_string(),
+                change.action.as_str(),
+                spond_before.map(to_json).transpose()?,
+            ],
+        )?;
+        self.log_change(change)
+    }
+
+    /// Appends a change to the audit log.
+    pub fn log_change(&self, change: &Change) -> rusqlite::Result<()> {
+        self.conn.execute(
+            "INSERT INTO changes (time, operator, team, spond_id, fixture_key, action, before, after)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            params![
+                change.time.to_rfc3339(),
+                change.operator,
+                change.team,
+                change.spond_id.to_string(),
+                change
+                    .fixture_key()
+                    .as_deref()
+                    .unwrap_or_default()
+                    .to_owned(),
+                change.action.as_str(),
+                change.before.as_ref().map(to_json).transpose()?,
+                change.after.as_ref().map(to_json).transpose()?,
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// The audit log in the order the changes were made, optionally only for
+    /// one team or one fixture.
+    pub fn history(
+        &self,
+        team: Option<&str>,
+        fixture_key: Option<&full_time::FixtureKey>,
+    ) -> rusqlite::Result<Vec<Change>> {
+        let mut statement = self.conn.prepare(
+            "SELECT time, operator, team, spond_id, action, before, after
+             FROM changes
+             WHERE (?1 IS NULL OR team = ?1 COLLATE NOCASE) AND (?2 IS NULL OR fixture_key = ?2)
+             ORDER BY id",
+        )?;
+        let changes = statement.query_map(params![team, fixture_key.map(|key| &**key)], |row| {
+            Ok(Change {
+                time: timestamp(row, 0)?,
+                operator: row.get(1)?,
+                team: row.get(2)?,
+                spond_id: spond::SpondId::new(row.get::<_, String>(3)?),
+                action: action(row, 4)?,
+                before: optional_json(row, 5)?,
+                after: optional_json(row, 6)?,
+            })
+        })?;
+        changes.collect()
+    }
+
+    /// The changes made in a run, in the order they were made.
+    pub fn journal_entries(&self, run_id: RunId) -> rusqlite::Result<Vec<JournalEntry>> {
+        let mut statement = self.conn.prepare(
+            "SELECT id, team, spond_id, action, spond, undone_time IS NOT NULL
+             FROM journal WHERE run_id = ?1 ORDER BY id",
+        )?;
+        let entries = statement.query_map([run_id.0], |row| {
+            Ok(JournalEntry {
+                id: row.get(0)?,
+                team: row.get(1)?,
+                spond_id: spond::SpondId::new(row.get::<_, String>(2)?),
+                action: action(row, 3)?,
+                spond: optional_json(row, 4)?,
+                undone: row.get(5)?,
+            })
+        })?;
+        entries.collect()
+    }
+
+    /// Marks a journaled change as undone, so that undoing its run again skips it.
+    pub fn mark_undone(&self, entry: &JournalEntry) -> rusqlite::Result<()> {
+        self.conn.execute(
+            "UPDATE journal SET undone_time = ?1 WHERE id = ?2",
+            params![Utc::now().to_rfc3339(), entry.id],
+        )?;
+        Ok(())
+    }
+
+    pub fn forget(&self, team: &str, id: &spond::SpondId) -> rusqlite::Result<()> {
+        self.conn.execute(
+            "DELETE FROM sponds WHERE team = ?1 AND spond_id = ?2",
+            params![team, id.to_string()],
+        )?;
+        Ok(())
+    }
+}
@@ -1,11 +1,39 @@
+use std::path::{Path, PathBuf};
+
 use figment::{
-    providers::{Format as _, Json},
+    providers::{Env, Format as _, Json, Toml, Yaml},
     Figment,
 };
+use serde::Serialize;
+
+/// The environment variable holding the path of the config file to load.
+pub const CONFIG_ENV_VAR: &str = "FULL_TIME_SPOND_SYNC_CONFIG";
+
+/// Prefix for environment variables overriding individual config values,
+/// e.g. `FULL_TIME_SPOND_SYNC_SPOND__GROUP_ID`.
+pub const ENV_PREFIX: &str = "FULL_TIME_SPOND_SYNC_";
+
+const CONFIG_FILE_STEM: &str = "teams";
+const CONFIG_EXTENSIONS: [&str; 4] = ["json", "toml", "yaml", "yml"];
+
+/// What to do with a Spond whose fixture is no longer on Full-Time.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+#[serde(tag = "policy", rename_all = "lowercase")]
+pub enum Removal {
+    /// Delete the Spond along with everyone's responses.
+    #[default]
+    Delete,
+    /// Cancel the Spond, keeping the responses, and send the invitees `reason`.
+    Cancel { reason: String },
+    /// Leave the Spond and only report it.
+    Report,
+}
 
 #[derive(Debug, Clone)]
 pub struct Spond {
+    pub group_id: crate::spond::GroupId,
     pub sub_group_id: crate::spond::SubGroupId,
+    pub removal: Removal,
 }
 
 #[derive(Debug, Clone)]
@@ -21,7 +49,14 @@ pub struct Team {
     pub spond: Spond,
 }
 
+#[derive(Debug, Clone)]
+pub struct Club {
+    pub teams: Vec<Team>,
+    pub venues: crate::venue::Registry,
+}
+
 mod config {
+    use rust_decimal::Decimal;
     use serde::Deserialize;
 
     #[derive(Debug, Clone, Deserialize)]
@@ -31,28 +66,61 @@ mod config {
         season_id: i32,
     }
 
-    impl Into<super::FullTime> for FullTime {
-        fn into(self) -> super::FullTime {
+    impl From<FullTime> for super::FullTime {
+        fn from(val: FullTime) -> Self {
             super::FullTime {
-                season_id: crate::full_time::SeasonId::new(self.season_id),
+                season_id: crate::full_time::SeasonId::new(val.season_id),
                 team: crate::full_time::Team {
-                    id: crate::full_time::TeamId::new(self.id),
-                    name: crate::full_time::TeamName::new(self.name),
+                    id: crate::full_time::TeamId::new(val.id),
+                    name: crate::full_time::TeamName::new(val.name),
                 },
             }
         }
     }
 
+    const DEFAULT_CANCELLATION_REASON: &str = "This fixture is no longer on Full-Time.";
+
+    #[derive(Debug, Clone, Deserialize)]
+    #[serde(tag = "policy", rename_all = "lowercase")]
+    pub enum Removal {
+        Delete,
+        Cancel { reason: Option<String> },
+        Report,
+    }
+
+    impl From<Removal> for super::Removal {
+        fn from(val: Removal) -> Self {
+            match val {
+                Removal::Delete => super::Removal::Delete,
+                Removal::Cancel { reason } => super::Removal::Cancel {
+                    reason: reason.unwrap_or_else(|| DEFAULT_CANCELLATION_REASON.to_owned()),
+                },
+                Removal::Report => super::Removal::Report,
+            }
+        }
+    }
+
     #[derive(Debug, Clone, Deserialize)]
     pub struct Spond {
+        group_id: Option<String>,
         sub_group_id: String,
+        removal: Option<Removal>,
     }
 
-    impl Into<super::Spond> for Spond {
-        fn into(self) -> super::Spond {
-            super::Spond {
+    impl Spond {
+        /// Takes the group and removal policy from `club` unless the team sets
+        /// its own, returning `None` if neither sets a group.
+        fn into_spond(self, club: &ClubSpond) -> Option<super::Spond> {
+            Some(super::Spond {
+                group_id: crate::spond::GroupId::new(
+                    self.group_id.as_deref().or(club.group_id.as_deref())?,
+                ),
                 sub_group_id: crate::spond::SubGroupId::new(self.sub_group_id),
-            }
+                removal: self
+                    .removal
+                    .or_else(|| club.removal.clone())
+                    .map_or_else(Default::default, Into::into),
+            })
         }
     }
 
@@ -63,28 +131,147 @@ mod config {
         spond: Spond,
     }
 
-    impl Into<super::Team> for Team {
-        fn into(self) -> super::Team {
-            super::Team {
+    impl Team {
+        fn into_team(self, club: &ClubSpond) -> Result<super::Team, crate::Error> {
+            let spond = self.spond.into_spond(club).ok_or_else(|| {
+                Box::new(figment::Error::from(format!(
+                    "Team '{}' has no Spond group_id, set one for the team or the club.",
+                    self.name
+                )))
+            })?;
+            Ok(super::Team {
                 name: self.name,
                 full_time: self.full_time.into(),
-                spond: self.spond.into(),
+                spond,
+            })
+        }
+    }
+
+    #[derive(Debug, Clone, Deserialize)]
+    pub struct Venue {
+        name: String,
+        patterns: Vec<String>,
+        feature: String,
+        address: String,
+        latitude: Decimal,
+        longitude: Decimal,
+    }
+
+    impl From<Venue> for crate::venue::Definition {
+        fn from(val: Venue) -> Self {
+            crate::venue::Definition {
+                name: val.name,
+                patterns: val.patterns,
+                feature: val.feature,
+                address: val.address,
+                latitude: val.latitude,
+                longitude: val.longitude,
             }
         }
     }
 
+    /// Spond settings shared by all of the club's teams unless a team overrides them.
+    #[derive(Debug, Clone, Default, Deserialize)]
+    pub struct ClubSpond {
+        group_id: Option<String>,
+        removal: Option<Removal>,
+    }
+
     #[derive(Debug, Clone, Deserialize)]
-    pub struct Teams {
-        pub teams: Vec<Team>,
+    pub struct Club {
+        #[serde(default)]
+        spond: ClubSpond,
+        teams: Vec<Team>,
+        venues: Option<Vec<Venue>>,
+    }
+
+    impl TryFrom<Club> for super::Club {
+        type Error = crate::Error;
+
+        fn try_from(val: Club) -> Result<Self, Self::Error> {
+            Ok(super::Club {
+                teams: val
+                    .teams
+                    .into_iter()
+                    .map(|t| t.into_team(&val.spond))
+                    .collect::<Result<_, _>>()?,
+                venues: val.venues.map_or_else(Default::default, |venues| {
+                    crate::venue::Registry::new(venues.into_iter().map(|v| v.into()).collect())
+                }),
+            })
+        }
     }
 }
 
-pub fn load() -> figment::Result<Vec<Team>> {
-    Ok(Figment::new()
-        .join(Json::file("src/teams.json"))
-        .extract::<config::Teams>()?
-        .teams
-        .into_iter()
-        .map(|t| t.into())
-        .collect())
+pub fn default_config_dir() -> Option<PathBuf> {
+    std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".config")))
+        .map(|dir| dir.join("full_time_spond_sync"))
+}
+
+fn file_provider(path: &Path) -> Result<Figment, Box<figment::Error>> {
+    match path.extension().and_then(|e| e.to_str()) {
+        Some("json") => Ok(Figment::from(Json::file_exact(path))),
+        Some("toml") => Ok(Figment::from(Toml::file_exact(path))),
+        Some("yaml" | "yml") => Ok(Figment::from(Yaml::file_exact(path))),
+        _ => Err(Box::new(figment::Error::from(format!(
+            "Unsupported config file format '{}', expected one of {}.",
+            path.display(),
+            CONFIG_EXTENSIONS.join(", ")
+        )))),
+    }
+}
+
+fn config_files(path: Option<&Path>) -> Result<Vec<PathBuf>, Box<figment::Error>> {
+    if let Some(path) = path
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os(CONFIG_ENV_VAR).map(PathBuf::from))
+    {
+        return if path.is_file() {
+            Ok(vec![path])
+        } else {
+            Err(Box::new(figment::Error::from(format!(
+                "Config file '{}' does not exist.",
+                path.display()
+            ))))
+        };
+    }
+    let dir = default_config_dir().ok_or_else(|| {
+        Box::new(figment::Error::from(
+            "Unable to determine the default config directory.".to_owned(),
+        ))
+    })?;
+    let files = CONFIG_EXTENSIONS
+        .iter()
+        .map(|ext| dir.join(CONFIG_FILE_STEM).with_extension(ext))
+        .filter(|f| f.is_file())
+        .collect::<Vec<_>>();
+    if files.is_empty() {
+        Err(Box::new(figment::Error::from(format!(
+            "No config file found, expected {}/{CONFIG_FILE_STEM}.{{{}}}.",
+            dir.display(),
+            CONFIG_EXTENSIONS.join(",")
+        ))))
+    } else {
+        Ok(files)
+    }
+}
+
+/// Loads the club's teams and venues from `path`, or from the file named by
+/// [`CONFIG_ENV_VAR`], or else from the JSON, TOML and YAML files found in the
+/// [`default_config_dir`]. Environment variables starting with [`ENV_PREFIX`]
+/// override values from the files. When no venues are configured the default
+/// venue registry is used.
+pub fn load(path: Option<&Path>) -> Result<Club, crate::Error> {
+    let mut figment = Figment::new();
+    for file in config_files(path)? {
+        figment = figment.merge(file_provider(&file)?);
+    }
+    let club = figment
+        .merge(Env::prefixed(ENV_PREFIX).ignore(&["CONFIG"]).split("__"))
+        .extract::<config::Club>()
+        .map_err(Box::new)?;
+    club.try_into()
 }
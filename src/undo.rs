@@ -0,0 +1,145 @@
+use chrono::Utc;
+use serde::Serialize;
+
+use crate::{
+    spond,
+    state::{self, Action, JournalEntry},
+    venue, Error, Team,
+};
+
+/// What undoing a run reverted, and the changes which couldn't be reverted.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct UndoReport {
+    pub run_id: state::RunId,
+    /// The changes which were reverted, latest first.
+    pub reverted: Vec<JournalEntry>,
+    pub errors: Vec<String>,
+}
+
+/// Reverts the changes a run made to Spond, latest first: created Sponds are
+/// deleted, updated Sponds are restored and deleted Sponds are recreated. A
+/// change which is reverted is marked as undone, so undoing the run again
+/// only retries the changes which failed.
+pub async fn undo(
+    run_id: state::RunId,
+    teams: &[Team],
+    venues: &venue::Registry,
+    spond_client: &spond::SpondClient,
+    state: &state::State,
+) -> Result<UndoReport, Error> {
+    let entries = state.journal_entries(run_id)?;
+    if entries.is_empty() {
+        return Err(Error::CannotUndo(format!(
+            "Run {run_id} made no changes to Spond."
+        )));
+    }
+    if entries.iter().all(|e| e.undone) {
+        return Err(Error::CannotUndo(format!(
+            "Run {run_id} has already been undone."
+        )));
+    }
+
+    let mut report = UndoReport {
+        run_id,
+        reverted: vec![],
+        errors: vec![],
+    };
+    for entry in entries.into_iter().rev().filter(|e| !e.undone) {
+        match revert(&entry, teams, venues, spond_client, state).await {
+            Ok(()) => {
+                state.mark_undone(&entry)?;
+                report.reverted.push(entry);
+            }
+            Err(e) => report.errors.push(format!(
+                "Unable to undo the {} of Spond {} for {}: {e}",
+                entry.action.as_str(),
+                entry.spond_id,
+                entry.team
+            )),
+        }
+    }
+    Ok(report)
+}
+
+async fn revert(
+    entry: &JournalEntry,
+    teams: &[Team],
+    venues: &venue::Registry,
+    spond_client: &spond::SpondClient,
+    state: &state::State,
+) -> Result<(), Error> {
+    let team = teams
+        .iter()
+        .find(|t| t.name == entry.team)
+        .ok_or_else(|| Error::CannotUndo(format!("{} is no longer configured", entry.team)))?;
+    let recorded_fixture = state
+        .records(&team.name)?
+        .remove(&entry.spond_id)
+        .map(|record| record.fixture);
+    let log_change = |action, spond_id: &spond::SpondId, before, after| {
+        state.log_change(&state::Change {
+            time: Utc::now(),
+            operator: spond_client.email().unwrap_or("unknown").to_owned(),
+            team: team.name.clone(),
+            spond_id: spond_id.clone(),
+            action,
+            before,
+            after,
+        })
+    };
+
+    match (entry.action, &entry.spond) {
+        (Action::Create, _) => {
+            spond_client.delete_spond(&entry.spond_id).await?;
+            state.forget(&team.name, &entry.spond_id)?;
+            log_change(Action::Delete, &entry.spond_id, recorded_fixture, None)?;
+        }
+        (Action::Update, Some(spond)) => {
+            spond_client.update_spond(spond.clone()).await?;
+            let fixture = spond.to_fixture(venues);
+            match &fixture {
+                Some(fixture) => state.record(&team.name, spond, fixture, spond)?,
+                None => state.forget(&team.name, &spond.id)?,
+            }
+            log_change(Action::Update, &spond.id, recorded_fixture, fixture)?;
+        }
+        (Action::Delete, Some(spond)) => {
+            let fixture = spond
+                .to_fixture(venues)
+                .ok_or_else(|| Error::CannotUndo("the deleted Spond wasn't a match".to_owned()))?;
+            let group = spond_client.get_group(&team.spond.group_id).await?;
+            let request = spond::CreateSpondRequest {
+                heading: spond.heading.clone(),
+                description: spond.description.clone(),
+                start_timestamp: spond.start_timestamp,
+                end_timestamp: spond.end_timestamp,
+                meetup_prior: spond.meetup_prior,
+                location: spond.location.clone(),
+                match_info: spond.match_info.clone(),
+                ..fixture.to_create_spond_request(&group, &team.spond.sub_group_id, venues)
+            };
+            let created = spond_client
+                .create_spond(
+                    request.clone(),
+                    &spond::IdempotencyKey::new(
+                        &team.spond.sub_group_id,
+                        &format!("undo/{}", entry.id),
+                    ),
+                )
+                .await?;
+            state.record(&team.name, &created, &fixture, &request)?;
+            log_change(Action::Create, &created.id, None, Some(fixture))?;
+        }
+        (Action::Cancel, _) => {
+            return Err(Error::CannotUndo(
+                "Spond can't reinstate a cancelled event, restore it in the app".to_owned(),
+            ))
+        }
+        (Action::Update | Action::Delete, None) => {
+            return Err(Error::CannotUndo(
+                "the Spond wasn't journaled before it was changed".to_owned(),
+            ))
+        }
+    }
+    Ok(())
+}
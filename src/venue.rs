@@ -0,0 +1,131 @@
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Definition {
+    /// The name used to identify this venue, e.g. in `full_time::Venue::Known`.
+    pub name: String,
+    /// Case insensitive fragments that must all appear in the Full-Time venue text.
+    pub patterns: Vec<String>,
+    /// The name of the location feature in Spond.
+    pub feature: String,
+    pub address: String,
+    pub latitude: Decimal,
+    pub longitude: Decimal,
+}
+
+impl Definition {
+    fn matches_full_time(&self, venue_text: &str) -> bool {
+        let venue_text = venue_text.to_lowercase();
+        !self.patterns.is_empty()
+            && self
+                .patterns
+                .iter()
+                .all(|p| venue_text.contains(&p.to_lowercase()))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Registry {
+    venues: Vec<Definition>,
+}
+
+impl Registry {
+    pub fn new(venues: Vec<Definition>) -> Self {
+        Self { venues }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Definition> {
+        self.venues.iter().find(|v| v.name == name)
+    }
+
+    /// Finds the first venue whose patterns all match the venue text from a Full-Time fixture.
+    pub fn find_by_full_time_text(&self, venue_text: &str) -> Option<&Definition> {
+        self.venues.iter().find(|v| v.matches_full_time(venue_text))
+    }
+
+    pub fn find_by_feature(&self, feature: &str) -> Option<&Definition> {
+        self.venues.iter().find(|v| v.feature == feature)
+    }
+}
+
+impl Default for Registry {
+    fn default() -> Self {
+        Self::new(vec![
+            Definition {
+                name: "Goals".to_owned(),
+                patterns: vec!["goals".to_owned()],
+                feature: "Goals Reading".to_owned(),
+                address: "Woodlands Avenue, Woodley, Reading".to_owned(),
+                latitude: dec!(51.453648),
+                longitude: dec!(-0.9185121),
+            },
+            Definition {
+                name: "KingsAcademy".to_owned(),
+                patterns: vec!["kings academy".to_owned()],
+                feature: "King’s Academy Prospect".to_owned(),
+                address: "King's Academy Prospect, Cockney Hill, Tilehurst, Reading RG30 4EX"
+                    .to_owned(),
+                latitude: dec!(51.44725212792346),
+                longitude: dec!(-1.01962455537632),
+            },
+            Definition {
+                name: "WoodfordPark3G".to_owned(),
+                patterns: vec!["woodford".to_owned(), "3g".to_owned()],
+                feature: "Woodford Park - 3G".to_owned(),
+                address: "Woodford Park Leisure Centre, Haddon Dr, Woodley, Reading, RG5 4LY"
+                    .to_owned(),
+                latitude: dec!(51.457008),
+                longitude: dec!(-0.9058048),
+            },
+            Definition {
+                name: "WoodfordParkGrass".to_owned(),
+                patterns: vec!["woodford".to_owned(), "grass".to_owned()],
+                feature: "Woodford Park - Grass".to_owned(),
+                address: "Woodford Park Leisure Centre, Haddon Dr, Woodley, Reading, RG5 4LY"
+                    .to_owned(),
+                latitude: dec!(51.457008),
+                longitude: dec!(-0.9058048),
+            },
+            Definition {
+                name: "CintraPark".to_owned(),
+                patterns: vec!["cintra park".to_owned()],
+                feature: "Cintra Park".to_owned(),
+                address: "Cintra Park, Reading, RG2 7ES".to_owned(),
+                latitude: dec!(51.442006),
+                longitude: dec!(-0.958364),
+            },
+            Definition {
+                name: "NorthStreetTheale".to_owned(),
+                patterns: vec!["north street theale".to_owned()],
+                feature: "North Street Theale".to_owned(),
+                address: "Englefield Rd, Theale, Reading RG7 5AS".to_owned(),
+                latitude: dec!(51.438580113890666),
+                longitude: dec!(-1.0837532493059963),
+            },
+        ])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn requires_all_patterns_to_match() {
+        let venues = Registry::default();
+        assert_eq!(
+            venues
+                .find_by_full_time_text("Woodford Park 3G Pitch")
+                .map(|v| v.name.as_str()),
+            Some("WoodfordPark3G")
+        );
+        assert_eq!(
+            venues
+                .find_by_full_time_text("Woodford Park Grass")
+                .map(|v| v.name.as_str()),
+            Some("WoodfordParkGrass")
+        );
+        assert_eq!(venues.find_by_full_time_text("Woodford Park"), None);
+    }
+}
@@ -0,0 +1,298 @@
+//! An in-process fake of the Spond API and Full-Time site for end-to-end sync tests.
+
+use chrono::{DateTime, Duration, NaiveTime, TimeZone, Utc};
+use chrono_tz::{Europe::London, Tz};
+use full_time_spond_sync::{
+    full_time, http, spond, state::State, team, venue, Error, OnEdit, SyncReport, SyncType, Team,
+};
+use serde_json::{json, Value};
+use wiremock::{
+    matchers::{method, path, path_regex},
+    Mock, MockServer, ResponseTemplate,
+};
+
+pub const TEAM_NAME: &str = "Twyford Comets FC U9 Jedis";
+
+pub fn team() -> Team {
+    Team {
+        name: "Jedis".to_owned(),
+        full_time: team::FullTime {
+            season_id: full_time::SeasonId::new(345055822),
+            team: full_time::Team {
+                id: full_time::TeamId::new(994929228),
+                name: full_time::TeamName::new(TEAM_NAME),
+            },
+        },
+        spond: team::Spond {
+            group_id: spond::GroupId::new("GROUP1"),
+            sub_group_id: spond::SubGroupId::new("SUBGROUP1"),
+            removal: team::Removal::Delete,
+        },
+    }
+}
+
+/// A London kick off time the given number of days from now, so that it's
+/// always treated as upcoming.
+pub fn kick_off_in(days: i64, hour: u32, minute: u32) -> DateTime<Tz> {
+    London
+        .from_local_datetime(
+            &(Utc::now() + Duration::days(days))
+                .with_timezone(&London)
+                .date_naive()
+                .and_time(NaiveTime::from_hms_opt(hour, minute, 0).unwrap()),
+        )
+        .single()
+        .unwrap()
+}
+
+#[derive(Clone)]
+pub struct FixtureRow<'a> {
+    pub id: Option<&'a str>,
+    pub typ: &'a str,
+    pub kick_off: DateTime<Tz>,
+    pub home: &'a str,
+    pub away: &'a str,
+    pub venue: &'a str,
+}
+
+/// Renders a `displayTeam.html` page with a fixtures table containing `rows`.
+pub fn full_time_page(rows: &[FixtureRow]) -> String {
+    let rows = rows
+        .iter()
+        .map(|row| {
+            let score = match row.id {
+                Some(id) => format!("<a href=\"/displayFixture.html?id={id}\">VS</a>"),
+                None => "VS".to_owned(),
+            };
+            format!(
+                "<tr>\
+                    <td>{}</td>\
+                    <td><span>{}</span><span>{}</span></td>\
+                    <td><a href=\"/displayTeam.html?id=1\">{}</a></td>\
+                    <td></td>\
+                    <td>{score}</td>\
+                    <td></td>\
+                    <td><a href=\"/displayTeam.html?id=2\">{}</a></td>\
+                    <td>{}</td>\
+                </tr>",
+                row.typ,
+                row.kick_off.format("%d/%m/%y"),
+                row.kick_off.format("%H:%M"),
+                row.home,
+                row.away,
+                row.venue
+            )
+        })
+        .collect::<String>();
+    format!(
+        "<html><body><div class=\"fixtures-table\"><table>\
+            <thead><tr><th>Type</th></tr></thead>\
+            <tbody>{rows}</tbody>\
+        </table></div></body></html>"
+    )
+}
+
+/// An existing Spond for a home league match against `opponent` at Woodford Park 3G.
+pub fn spond_json(
+    id: &str,
+    fixture_key: Option<&str>,
+    opponent: &str,
+    kick_off: DateTime<Tz>,
+) -> Value {
+    let start = kick_off.with_timezone(&Utc);
+    json!({
+        "id": id,
+        "creatorId": "PROFILE_COACH",
+        "owners": [],
+        "heading": format!("Jedis - {opponent}"),
+        "description": fixture_key.map(|key| format!("Full-Time fixture: {key}")),
+        "startTimestamp": start,
+        "endTimestamp": start + Duration::hours(1),
+        "meetupTimestamp": null,
+        "meetupPrior": 15,
+        "location": {
+            "feature": "Woodford Park - 3G",
+            "address": "Woodford Park Leisure Centre, Haddon Dr, Woodley, Reading, RG5 4LY",
+            "latitude": 51.457008,
+            "longitude": -0.9058048
+        },
+        "matchInfo": {
+            "teamName": "Jedis",
+            "opponentName": opponent,
+            "teamColour": null,
+            "opponentColour": null,
+            "type": "HOME",
+            "scoresSet": false,
+            "scoresSetEver": false,
+            "teamScore": null,
+            "opponentScore": null,
+            "scoresPublic": true,
+            "scoresFinal": false
+        },
+        "matchEvent": true,
+        "createdTime": "2023-08-01T12:00:00Z",
+        "expired": false
+    })
+}
+
+/// A request received by one of the fake servers.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Call {
+    pub method: String,
+    pub path: String,
+    pub body: Option<Value>,
+    pub idempotency_key: Option<String>,
+}
+
+/// Retries straight away, so that tests of failed requests stay fast.
+pub fn sender() -> http::Sender {
+    http::Sender::new(
+        http::RetryPolicy {
+            initial_backoff: std::time::Duration::ZERO,
+            ..http::RetryPolicy::default()
+        },
+        http::RateLimit::unlimited(),
+    )
+}
+
+pub struct FakeServers {
+    pub full_time: MockServer,
+    pub spond: MockServer,
+}
+
+impl FakeServers {
+    /// Starts a Full-Time server serving `page` and a Spond server whose group
+    /// is `tests/snapshots/spond/group.json` and which lists `sponds`.
+    pub async fn start(page: String, sponds: Vec<Value>) -> Self {
+        let full_time = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/displayTeam.html"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(page))
+            .mount(&full_time)
+            .await;
+
+        let spond = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/login"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "loginToken": "LOGIN_TOKEN",
+                "passwordToken": "PASSWORD_TOKEN"
+            })))
+            .mount(&spond)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/group/GROUP1"))
+            .respond_with(ResponseTemplate::new(200).set_body_raw(
+                include_str!("../snapshots/spond/group.json"),
+                "application/json",
+            ))
+            .mount(&spond)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/sponds"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(sponds))
+            .mount(&spond)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/sponds"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(spond_json(
+                "SPOND_CREATED",
+                None,
+                "Created",
+                kick_off_in(7, 10, 0),
+            )))
+            .mount(&spond)
+            .await;
+        Mock::given(method("POST"))
+            .and(path_regex("^/sponds/[^/]+$"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({})))
+            .mount(&spond)
+            .await;
+        Mock::given(method("POST"))
+            .and(path_regex("^/sponds/[^/]+/cancel$"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({})))
+            .mount(&spond)
+            .await;
+        Mock::given(method("DELETE"))
+            .and(path_regex("^/sponds/[^/]+$"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&spond)
+            .await;
+
+        Self { full_time, spond }
+    }
+
+    pub fn full_time_client(&self) -> full_time::FullTimeClient {
+        full_time::FullTimeClient::new(self.full_time.uri()).with_sender(sender())
+    }
+
+    pub async fn spond_client(&self) -> spond::SpondClient {
+        self.spond_client_with_timeout(http::DEFAULT_TIMEOUT).await
+    }
+
+    /// Syncs `team` between the servers with the default venues.
+    pub async fn sync(
+        &self,
+        team: &Team,
+        state: &State,
+        on_edit: OnEdit,
+        sync_type: SyncType,
+    ) -> Result<SyncReport, Error> {
+        full_time_spond_sync::sync(
+            team,
+            &venue::Registry::default(),
+            &self.full_time_client(),
+            &self.spond_client().await,
+            state,
+            on_edit,
+            sync_type,
+        )
+        .await
+    }
+
+    /// A logged in Spond client whose requests time out after `timeout`.
+    pub async fn spond_client_with_timeout(
+        &self,
+        timeout: std::time::Duration,
+    ) -> spond::SpondClient {
+        let mut client =
+            spond::SpondClient::with_http_client(http::client(timeout), self.spond.uri())
+                .with_sender(sender());
+        client
+            .login(&spond::UserCredentials {
+                email: "coach@example.com".to_owned(),
+                password: "password".to_owned(),
+            })
+            .await
+            .unwrap();
+        client
+    }
+
+    /// The requests made to the Spond API, in order.
+    pub async fn spond_calls(&self) -> Vec<Call> {
+        self.spond
+            .received_requests()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|r| Call {
+                method: r.method.to_string(),
+                path: r.url.path().to_owned(),
+                body: serde_json::from_slice(&r.body).ok(),
+                idempotency_key: r
+                    .headers
+                    .get("Idempotency-Key")
+                    .map(|v| v.to_str().unwrap().to_owned()),
+            })
+            .collect()
+    }
+
+    /// The requests which create, update or delete Sponds, in order.
+    pub async fn spond_writes(&self) -> Vec<Call> {
+        self.spond_calls()
+            .await
+            .into_iter()
+            .filter(|c| c.method != "GET" && c.path != "/login")
+            .collect()
+    }
+}
@@ -0,0 +1,167 @@
+use chrono::{DateTime, TimeZone, Utc};
+use chrono_tz::{Europe::London, Tz};
+use full_time_spond_sync::{
+    full_time::{
+        parse_upcoming_fixtures, Fixture, FixtureId, FixtureSide, FixtureType, ParseError,
+        TeamName, UpcomingFixtures, Venue,
+    },
+    venue,
+};
+
+fn parse(html: &str) -> Result<UpcomingFixtures, ParseError> {
+    parse_upcoming_fixtures(
+        html,
+        &TeamName::new("Twyford Comets FC U9 Jedis"),
+        &venue::Registry::default(),
+        Utc.with_ymd_and_hms(2023, 10, 1, 0, 0, 0).unwrap(),
+    )
+}
+
+fn kick_off(day: u32, month: u32, hour: u32, minute: u32) -> DateTime<Tz> {
+    London
+        .with_ymd_and_hms(2023, month, day, hour, minute, 0)
+        .unwrap()
+}
+
+fn known(name: &str) -> Venue {
+    Venue::Known(name.to_owned())
+}
+
+#[test]
+fn league_fixtures() {
+    let upcoming = parse(include_str!("snapshots/full_time/league.html")).unwrap();
+    assert_eq!(
+        upcoming.fixtures,
+        vec![
+            Fixture {
+                id: Some(FixtureId::new("27100002")),
+                typ: FixtureType::League,
+                side: FixtureSide::Home,
+                date_time: kick_off(7, 10, 10, 0),
+                opposition: "Woodley United U9 Reds".to_owned(),
+                venue: known("WoodfordPark3G"),
+            },
+            Fixture {
+                id: Some(FixtureId::new("27100003")),
+                typ: FixtureType::League,
+                side: FixtureSide::Away,
+                date_time: kick_off(14, 10, 9, 30),
+                opposition: "Emmbrook Sports U9 Tigers".to_owned(),
+                venue: Venue::Other {
+                    name: "Emmbrook Sports Club".to_owned(),
+                    address: "Lowther Road, Wokingham RG41 1JB".to_owned(),
+                },
+            },
+            Fixture {
+                id: Some(FixtureId::new("27100004")),
+                typ: FixtureType::League,
+                side: FixtureSide::Home,
+                date_time: kick_off(21, 10, 11, 0),
+                opposition: "Reading City U9 Blues".to_owned(),
+                venue: known("Goals"),
+            },
+            Fixture {
+                id: None,
+                typ: FixtureType::League,
+                side: FixtureSide::Away,
+                date_time: kick_off(28, 10, 9, 0),
+                opposition: "Finchampstead U9 Falcons".to_owned(),
+                venue: known("CintraPark"),
+            },
+        ]
+    );
+    assert_eq!(upcoming.skipped, vec![]);
+}
+
+#[test]
+fn cup_fixtures() {
+    let upcoming = parse(include_str!("snapshots/full_time/cup.html")).unwrap();
+    assert_eq!(
+        upcoming.fixtures,
+        vec![
+            Fixture {
+                id: Some(FixtureId::new("27200001")),
+                typ: FixtureType::Cup,
+                side: FixtureSide::Home,
+                date_time: kick_off(5, 11, 10, 30),
+                opposition: "Woodley United U9 Reds".to_owned(),
+                venue: known("WoodfordParkGrass"),
+            },
+            Fixture {
+                id: Some(FixtureId::new("27200002")),
+                typ: FixtureType::Cup,
+                side: FixtureSide::Away,
+                date_time: kick_off(19, 11, 10, 30),
+                opposition: "Burghfield U9 Badgers".to_owned(),
+                venue: known("NorthStreetTheale"),
+            },
+        ]
+    );
+    assert_eq!(upcoming.skipped, vec![]);
+}
+
+#[test]
+fn postponed_fixtures_are_skipped() {
+    let upcoming = parse(include_str!("snapshots/full_time/postponed.html")).unwrap();
+    assert_eq!(
+        upcoming.fixtures,
+        vec![
+            Fixture {
+                id: Some(FixtureId::new("27300001")),
+                typ: FixtureType::League,
+                side: FixtureSide::Home,
+                date_time: kick_off(14, 10, 9, 30),
+                opposition: "Woodley United U9 Reds".to_owned(),
+                venue: known("WoodfordPark3G"),
+            },
+            Fixture {
+                id: Some(FixtureId::new("27300004")),
+                typ: FixtureType::League,
+                side: FixtureSide::Home,
+                date_time: kick_off(28, 10, 9, 0),
+                opposition: "Finchampstead U9 Falcons".to_owned(),
+                venue: known("KingsAcademy"),
+            },
+        ]
+    );
+    assert_eq!(
+        upcoming
+            .skipped
+            .iter()
+            .map(|s| (
+                s.row,
+                s.reason.clone(),
+                s.id.clone(),
+                s.opposition.as_deref()
+            ))
+            .collect::<Vec<_>>(),
+        vec![
+            (
+                1,
+                ParseError::MissingDateTime,
+                None,
+                Some("Reading City U9 Blues")
+            ),
+            (
+                2,
+                ParseError::InvalidDateTime("TBC TBC".to_owned()),
+                None,
+                Some("Emmbrook Sports U9 Tigers")
+            ),
+        ]
+    );
+}
+
+#[test]
+fn empty_page_has_no_fixtures() {
+    let upcoming = parse(include_str!("snapshots/full_time/empty.html")).unwrap();
+    assert_eq!(upcoming, UpcomingFixtures::default());
+}
+
+#[test]
+fn multiple_fixture_tables_are_rejected() {
+    assert_eq!(
+        parse(include_str!("snapshots/full_time/multiple_tables.html")),
+        Err(ParseError::MultipleFixtureTables(2))
+    );
+}
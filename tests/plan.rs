@@ -0,0 +1,118 @@
+mod common;
+
+use common::{full_time_page, kick_off_in, spond_json, team, FakeServers, FixtureRow, TEAM_NAME};
+use full_time_spond_sync::{
+    apply,
+    full_time::FixtureKey,
+    plan::{self, Conflict, Plan, TeamPlan},
+    spond,
+    state::State,
+    venue, OnEdit, SyncType,
+};
+use serde_json::Value;
+
+const WOODLEY: &str = "Woodley United U9 Reds";
+const EMMBROOK: &str = "Emmbrook Sports U9 Tigers";
+
+/// Full-Time has a rescheduled fixture and a new fixture.
+async fn start_servers(sponds: Vec<Value>) -> FakeServers {
+    let page = full_time_page(&[
+        FixtureRow {
+            id: Some("100"),
+            typ: "L",
+            kick_off: kick_off_in(14, 10, 0),
+            home: TEAM_NAME,
+            away: WOODLEY,
+            venue: "Woodford Park 3G",
+        },
+        FixtureRow {
+            id: Some("101"),
+            typ: "L",
+            kick_off: kick_off_in(21, 9, 30),
+            home: TEAM_NAME,
+            away: EMMBROOK,
+            venue: "Woodford Park 3G",
+        },
+    ]);
+    FakeServers::start(page, sponds).await
+}
+
+/// Makes a plan against the given servers and round trips it through JSON.
+async fn make_plan(servers: &FakeServers) -> Plan {
+    let report = servers
+        .sync(
+            &team(),
+            &State::open_in_memory().unwrap(),
+            OnEdit::default(),
+            SyncType::Dry,
+        )
+        .await
+        .unwrap();
+    let plan = Plan::new(vec![TeamPlan::from_report(&report)]);
+    serde_json::from_str(&serde_json::to_string(&plan).unwrap()).unwrap()
+}
+
+fn woodley_spond() -> Value {
+    spond_json("SPOND_A", Some("id:100"), WOODLEY, kick_off_in(14, 9, 30))
+}
+
+#[tokio::test]
+async fn applies_an_unchanged_plan() {
+    let servers = start_servers(vec![woodley_spond()]).await;
+    let plan = make_plan(&servers).await;
+    let spond_client = servers.spond_client().await;
+
+    assert_eq!(
+        plan::verify(&plan.teams[0], &team(), &spond_client)
+            .await
+            .unwrap(),
+        vec![]
+    );
+    apply(
+        &team(),
+        &venue::Registry::default(),
+        &spond_client,
+        &State::open_in_memory().unwrap(),
+        plan.teams[0].diff.clone(),
+        OnEdit::default(),
+        SyncType::Real,
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(
+        servers
+            .spond_writes()
+            .await
+            .iter()
+            .map(|c| (c.method.as_str(), c.path.as_str()))
+            .collect::<Vec<_>>(),
+        vec![("POST", "/sponds"), ("POST", "/sponds/SPOND_A")]
+    );
+}
+
+#[tokio::test]
+async fn refuses_a_plan_whose_sponds_have_changed() {
+    let plan = make_plan(&start_servers(vec![woodley_spond()]).await).await;
+
+    let mut edited = woodley_spond();
+    edited["meetupPrior"] = 30.into();
+    let servers = start_servers(vec![
+        edited,
+        spond_json("SPOND_B", Some("id:101"), EMMBROOK, kick_off_in(21, 9, 30)),
+    ])
+    .await;
+
+    assert_eq!(
+        plan::verify(&plan.teams[0], &team(), &servers.spond_client().await)
+            .await
+            .unwrap(),
+        vec![
+            Conflict::Changed(spond::SpondId::new("SPOND_A")),
+            Conflict::AlreadyCreated {
+                key: FixtureKey::new("id:101"),
+                spond: spond::SpondId::new("SPOND_B"),
+            },
+        ]
+    );
+}
@@ -0,0 +1,833 @@
+mod common;
+
+use std::time::Duration;
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use common::{full_time_page, kick_off_in, spond_json, team, FakeServers, FixtureRow, TEAM_NAME};
+use full_time_spond_sync::{
+    full_time,
+    snapshot::Field,
+    spond,
+    state::{Action, State},
+    sync, team as team_config,
+    undo::undo,
+    venue, Error, FixtureChange, OnEdit, SpondApiError, SyncReport, SyncType,
+};
+use reqwest::StatusCode;
+use wiremock::{
+    matchers::{method, path},
+    Mock, MockServer, ResponseTemplate,
+};
+
+const WOODLEY: &str = "Woodley United U9 Reds";
+const READING: &str = "Reading City U9 Blues";
+const EMMBROOK: &str = "Emmbrook Sports U9 Tigers";
+const FINCHAMPSTEAD: &str = "Finchampstead U9 Falcons";
+
+/// Full-Time has an unchanged fixture, a rescheduled fixture and a new away
+/// fixture, while Spond also has an event for a fixture which was removed.
+async fn start_servers() -> FakeServers {
+    let page = full_time_page(&[
+        FixtureRow {
+            id: Some("100"),
+            typ: "L",
+            kick_off: kick_off_in(14, 9, 30),
+            home: TEAM_NAME,
+            away: WOODLEY,
+            venue: "Woodford Park 3G",
+        },
+        FixtureRow {
+            id: Some("101"),
+            typ: "L",
+            kick_off: kick_off_in(21, 10, 0),
+            home: TEAM_NAME,
+            away: READING,
+            venue: "Woodford Park 3G",
+        },
+        FixtureRow {
+            id: Some("102"),
+            typ: "L",
+            kick_off: kick_off_in(28, 9, 30),
+            home: EMMBROOK,
+            away: TEAM_NAME,
+            venue: "Emmbrook Sports Club",
+        },
+    ]);
+    let sponds = vec![
+        spond_json("SPOND_A", Some("id:100"), WOODLEY, kick_off_in(14, 9, 30)),
+        spond_json("SPOND_B", Some("id:101"), READING, kick_off_in(20, 9, 0)),
+        spond_json(
+            "SPOND_D",
+            Some("id:99"),
+            FINCHAMPSTEAD,
+            kick_off_in(35, 9, 0),
+        ),
+    ];
+    FakeServers::start(page, sponds).await
+}
+
+#[tokio::test]
+async fn real_sync_creates_updates_and_deletes_only_what_changed() {
+    let servers = start_servers().await;
+
+    servers
+        .sync(
+            &team(),
+            &State::open_in_memory().unwrap(),
+            OnEdit::default(),
+            SyncType::Real,
+        )
+        .await
+        .unwrap();
+
+    let writes = servers.spond_writes().await;
+    assert_eq!(
+        writes
+            .iter()
+            .map(|c| (c.method.as_str(), c.path.as_str()))
+            .collect::<Vec<_>>(),
+        vec![
+            ("POST", "/sponds"),
+            ("POST", "/sponds/SPOND_B"),
+            ("DELETE", "/sponds/SPOND_D"),
+        ]
+    );
+
+    let created = writes[0].body.as_ref().unwrap();
+    assert_eq!(created["heading"], format!("Jedis - {EMMBROOK}"));
+    assert_eq!(created["description"], "Full-Time fixture: id:102");
+    assert_eq!(created["matchInfo"]["type"], "AWAY");
+    assert_eq!(created["location"]["feature"], "Emmbrook Sports Club");
+    assert_eq!(created["owners"][0]["id"], "PROFILE_COACH");
+    assert_eq!(
+        created["recipients"]["groupMembers"],
+        serde_json::json!(["MEMBER_PLAYER1"])
+    );
+
+    let updated = writes[1].body.as_ref().unwrap();
+    assert_eq!(
+        serde_json::from_value::<DateTime<Utc>>(updated["startTimestamp"].clone()).unwrap(),
+        kick_off_in(21, 10, 0)
+    );
+    assert_eq!(updated["description"], "Full-Time fixture: id:101");
+}
+
+#[tokio::test]
+async fn makes_the_remaining_changes_after_one_fails() {
+    let servers = start_servers().await;
+    Mock::given(method("POST"))
+        .and(path("/sponds/SPOND_B"))
+        .respond_with(
+            ResponseTemplate::new(400)
+                .insert_header("X-Request-Id", "REQUEST1")
+                .set_body_json(serde_json::json!({"message": "Invalid owner id"})),
+        )
+        .with_priority(1)
+        .mount(&servers.spond)
+        .await;
+    let state = state_with_spond_b_synced(spond_json(
+        "SPOND_B",
+        Some("id:101"),
+        READING,
+        kick_off_in(20, 9, 0),
+    ));
+    let spond_b = spond::SpondId::new("SPOND_B");
+    let record_before = state.records("Jedis").unwrap().remove(&spond_b).unwrap();
+
+    let report = servers
+        .sync(&team(), &state, OnEdit::default(), SyncType::Real)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        servers
+            .spond_writes()
+            .await
+            .iter()
+            .map(|c| (c.method.as_str(), c.path.as_str()))
+            .collect::<Vec<_>>(),
+        vec![
+            ("POST", "/sponds"),
+            ("POST", "/sponds/SPOND_B"),
+            ("DELETE", "/sponds/SPOND_D"),
+        ]
+    );
+    assert_eq!(report.created.len(), 1);
+    assert_eq!(report.updated, vec![]);
+    assert_eq!(report.deleted.len(), 1);
+    assert_eq!(report.errors.len(), 1);
+    assert!(report.errors[0].starts_with(&format!("Failed to update Jedis - {READING}")));
+    assert_eq!(
+        report.spond_errors,
+        vec![SpondApiError {
+            status: StatusCode::BAD_REQUEST,
+            endpoint: "POST /sponds/SPOND_B".to_owned(),
+            request_id: Some("REQUEST1".to_owned()),
+            message: "Invalid owner id".to_owned(),
+        }]
+    );
+    assert_eq!(
+        serde_json::to_value(&report).unwrap()["spond_errors"][0]["status"],
+        400
+    );
+    assert_eq!(
+        state.records("Jedis").unwrap().remove(&spond_b),
+        Some(record_before)
+    );
+}
+
+#[tokio::test]
+async fn retries_server_errors_from_spond_and_full_time() {
+    let servers = start_servers().await;
+    Mock::given(method("GET"))
+        .and(path("/displayTeam.html"))
+        .respond_with(ResponseTemplate::new(504))
+        .with_priority(1)
+        .up_to_n_times(1)
+        .mount(&servers.full_time)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/sponds"))
+        .respond_with(ResponseTemplate::new(429).insert_header("Retry-After", "0"))
+        .with_priority(1)
+        .up_to_n_times(2)
+        .mount(&servers.spond)
+        .await;
+
+    let report = servers
+        .sync(
+            &team(),
+            &State::open_in_memory().unwrap(),
+            OnEdit::default(),
+            SyncType::Dry,
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(report.upcoming_full_time, 3);
+    assert_eq!(report.upcoming_spond, 3);
+    assert_eq!(
+        servers
+            .spond_calls()
+            .await
+            .iter()
+            .filter(|c| c.path == "/sponds")
+            .count(),
+        3
+    );
+}
+
+#[tokio::test]
+async fn retries_spond_requests_which_time_out() {
+    let servers = start_servers().await;
+    Mock::given(method("GET"))
+        .and(path("/sponds"))
+        .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_secs(5)))
+        .with_priority(1)
+        .up_to_n_times(1)
+        .mount(&servers.spond)
+        .await;
+
+    let report = sync(
+        &team(),
+        &venue::Registry::default(),
+        &servers.full_time_client(),
+        &servers
+            .spond_client_with_timeout(Duration::from_millis(500))
+            .await,
+        &State::open_in_memory().unwrap(),
+        OnEdit::default(),
+        SyncType::Dry,
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(report.upcoming_spond, 3);
+    assert_eq!(
+        servers
+            .spond_calls()
+            .await
+            .iter()
+            .filter(|c| c.path == "/sponds")
+            .count(),
+        2
+    );
+}
+
+#[tokio::test]
+async fn only_rejected_credentials_fail_login_as_auth_errors() {
+    for (status, rejected) in [
+        (400, true),
+        (401, true),
+        (403, true),
+        (429, false),
+        (500, false),
+    ] {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/login"))
+            .respond_with(ResponseTemplate::new(status).insert_header("Retry-After", "0"))
+            .mount(&server)
+            .await;
+
+        let result = spond::SpondClient::new(server.uri())
+            .with_sender(common::sender())
+            .login(&spond::UserCredentials {
+                email: "coach@example.com".to_owned(),
+                password: "wrong".to_owned(),
+            })
+            .await;
+
+        assert_eq!(
+            matches!(result, Err(Error::SpondAuth(_))),
+            rejected,
+            "{status}: {result:?}"
+        );
+    }
+}
+
+#[tokio::test]
+async fn keeps_the_status_and_body_of_spond_errors() {
+    let servers = start_servers().await;
+    Mock::given(method("GET"))
+        .and(path("/sponds"))
+        .respond_with(ResponseTemplate::new(400).set_body_string("invalid subGroupId"))
+        .with_priority(1)
+        .mount(&servers.spond)
+        .await;
+
+    let result = servers
+        .sync(
+            &team(),
+            &State::open_in_memory().unwrap(),
+            OnEdit::default(),
+            SyncType::Dry,
+        )
+        .await;
+
+    assert!(matches!(
+        result,
+        Err(Error::SpondApi(e)) if e.status == 400 && e.message == "invalid subGroupId"
+    ));
+}
+
+#[tokio::test]
+async fn distinguishes_a_full_time_markup_change() {
+    let table = "<div class=\"fixtures-table\"><table></table></div>";
+    let servers =
+        FakeServers::start(format!("<html><body>{table}{table}</body></html>"), vec![]).await;
+
+    let result = servers
+        .sync(
+            &team(),
+            &State::open_in_memory().unwrap(),
+            OnEdit::default(),
+            SyncType::Dry,
+        )
+        .await;
+
+    assert!(matches!(result, Err(Error::FullTimeParse(_))));
+}
+
+#[tokio::test]
+async fn creates_with_an_idempotency_key_for_the_fixture() {
+    let servers = start_servers().await;
+    for _ in 0..2 {
+        servers
+            .sync(
+                &team(),
+                &State::open_in_memory().unwrap(),
+                OnEdit::default(),
+                SyncType::Real,
+            )
+            .await
+            .unwrap();
+    }
+
+    let keys = servers
+        .spond_writes()
+        .await
+        .into_iter()
+        .filter(|c| c.path == "/sponds")
+        .map(|c| c.idempotency_key.unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(keys.len(), 2);
+    assert_eq!(keys[0], keys[1]);
+}
+
+/// Syncs a page of `rows` against the Sponds for id:100, id:99 and id:98,
+/// returning the report and the Sponds which were removed.
+async fn sync_page(rows: &[FixtureRow<'_>]) -> (SyncReport, Vec<String>) {
+    let servers = FakeServers::start(
+        full_time_page(rows),
+        vec![
+            spond_json("SPOND_A", Some("id:100"), WOODLEY, kick_off_in(14, 9, 30)),
+            spond_json(
+                "SPOND_D",
+                Some("id:99"),
+                FINCHAMPSTEAD,
+                kick_off_in(35, 9, 0),
+            ),
+            spond_json("SPOND_E", Some("id:98"), EMMBROOK, kick_off_in(42, 9, 0)),
+        ],
+    )
+    .await;
+    let report = servers
+        .sync(
+            &team(),
+            &State::open_in_memory().unwrap(),
+            OnEdit::default(),
+            SyncType::Real,
+        )
+        .await
+        .unwrap();
+    let removed = servers
+        .spond_writes()
+        .await
+        .into_iter()
+        .filter(|c| c.method == "DELETE")
+        .map(|c| c.path)
+        .collect();
+    (report, removed)
+}
+
+#[tokio::test]
+async fn keeps_sponds_when_rows_are_skipped() {
+    let woodley = FixtureRow {
+        id: Some("100"),
+        typ: "L",
+        kick_off: kick_off_in(14, 9, 30),
+        home: TEAM_NAME,
+        away: WOODLEY,
+        venue: "Woodford Park 3G",
+    };
+    let postponed = FixtureRow {
+        id: Some("99"),
+        typ: "TBC",
+        kick_off: kick_off_in(35, 9, 0),
+        home: TEAM_NAME,
+        away: FINCHAMPSTEAD,
+        venue: "Woodford Park 3G",
+    };
+
+    let (report, removed) = sync_page(&[woodley.clone(), postponed]).await;
+    assert_eq!(report.skipped.len(), 1);
+    assert_eq!(removed, vec!["/sponds/SPOND_E"]);
+    assert_eq!(
+        report.kept.iter().map(|s| s.id.clone()).collect::<Vec<_>>(),
+        vec![spond::SpondId::new("SPOND_D")]
+    );
+
+    // A postponed row without a fixture id is matched by its opposition.
+    let postponed_without_id = FixtureRow {
+        id: None,
+        typ: "TBC",
+        kick_off: kick_off_in(35, 9, 0),
+        home: TEAM_NAME,
+        away: FINCHAMPSTEAD,
+        venue: "Woodford Park 3G",
+    };
+    let (report, removed) = sync_page(&[woodley, postponed_without_id]).await;
+    assert_eq!(removed, vec!["/sponds/SPOND_E"]);
+    assert_eq!(
+        report.kept.iter().map(|s| s.id.clone()).collect::<Vec<_>>(),
+        vec![spond::SpondId::new("SPOND_D")]
+    );
+
+    // As when Full-Time's markup changes and no row can be parsed.
+    let unparseable = |id, away| FixtureRow {
+        id: Some(id),
+        typ: "?",
+        kick_off: kick_off_in(14, 9, 30),
+        home: TEAM_NAME,
+        away,
+        venue: "Woodford Park 3G",
+    };
+    let (report, removed) = sync_page(&[
+        unparseable("100", WOODLEY),
+        unparseable("99", FINCHAMPSTEAD),
+    ])
+    .await;
+    assert_eq!(removed, Vec::<String>::new());
+    assert_eq!(report.kept.len(), 3);
+}
+
+#[tokio::test]
+async fn real_sync_logs_each_change() {
+    let servers = start_servers().await;
+    let state = State::open_in_memory().unwrap();
+
+    servers
+        .sync(&team(), &state, OnEdit::default(), SyncType::Real)
+        .await
+        .unwrap();
+
+    let history = state.history(Some("jedis"), None).unwrap();
+    assert_eq!(
+        history
+            .iter()
+            .map(|c| (c.action, c.spond_id.to_string(), c.operator.as_str()))
+            .collect::<Vec<_>>(),
+        vec![
+            (
+                Action::Create,
+                "SPOND_CREATED".to_owned(),
+                "coach@example.com"
+            ),
+            (Action::Update, "SPOND_B".to_owned(), "coach@example.com"),
+            (Action::Delete, "SPOND_D".to_owned(), "coach@example.com"),
+        ]
+    );
+    assert_eq!(
+        history[1].changes(),
+        vec![FixtureChange::KickOff {
+            before: kick_off_in(20, 9, 0),
+            after: kick_off_in(21, 10, 0),
+        }]
+    );
+    assert_eq!(
+        state
+            .history(None, Some(&full_time::FixtureKey::new("id:99")))
+            .unwrap()
+            .iter()
+            .map(|c| c.action)
+            .collect::<Vec<_>>(),
+        vec![Action::Delete]
+    );
+}
+
+#[tokio::test]
+async fn undo_reverts_a_real_sync() {
+    let servers = start_servers().await;
+    let spond_client = servers.spond_client().await;
+    let state = State::open_in_memory().unwrap();
+    servers
+        .sync(&team(), &state, OnEdit::default(), SyncType::Real)
+        .await
+        .unwrap();
+    let run_id = state.run_id().unwrap();
+
+    let report = undo(
+        run_id,
+        &[team()],
+        &venue::Registry::default(),
+        &spond_client,
+        &state,
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(report.errors, Vec::<String>::new());
+    let writes = servers.spond_writes().await;
+    assert_eq!(
+        writes[3..]
+            .iter()
+            .map(|c| (c.method.as_str(), c.path.as_str()))
+            .collect::<Vec<_>>(),
+        vec![
+            ("POST", "/sponds"),
+            ("POST", "/sponds/SPOND_B"),
+            ("DELETE", "/sponds/SPOND_CREATED"),
+        ]
+    );
+    let recreated = writes[3].body.as_ref().unwrap();
+    assert_eq!(recreated["heading"], format!("Jedis - {FINCHAMPSTEAD}"));
+    assert_eq!(recreated["description"], "Full-Time fixture: id:99");
+    assert_eq!(
+        serde_json::from_value::<DateTime<Utc>>(
+            writes[4].body.as_ref().unwrap()["startTimestamp"].clone()
+        )
+        .unwrap(),
+        kick_off_in(20, 9, 0)
+    );
+    assert!(undo(
+        run_id,
+        &[team()],
+        &venue::Registry::default(),
+        &spond_client,
+        &state,
+    )
+    .await
+    .is_err());
+}
+
+#[tokio::test]
+async fn cancels_removed_fixtures_with_the_reason() {
+    let servers = start_servers().await;
+    let mut team = team();
+    team.spond.removal = team_config::Removal::Cancel {
+        reason: "Removed from Full-Time".to_owned(),
+    };
+
+    servers
+        .sync(
+            &team,
+            &State::open_in_memory().unwrap(),
+            OnEdit::default(),
+            SyncType::Real,
+        )
+        .await
+        .unwrap();
+
+    let writes = servers.spond_writes().await;
+    assert_eq!(
+        writes
+            .iter()
+            .map(|c| (c.method.as_str(), c.path.as_str()))
+            .collect::<Vec<_>>(),
+        vec![
+            ("POST", "/sponds"),
+            ("POST", "/sponds/SPOND_B"),
+            ("POST", "/sponds/SPOND_D/cancel"),
+        ]
+    );
+    assert_eq!(
+        writes[2].body,
+        Some(serde_json::json!({
+            "quiet": false,
+            "cancellationMessage": "Removed from Full-Time"
+        }))
+    );
+}
+
+#[tokio::test]
+async fn only_reports_removed_fixtures_when_configured_to() {
+    let servers = start_servers().await;
+    let mut team = team();
+    team.spond.removal = team_config::Removal::Report;
+
+    let report = servers
+        .sync(
+            &team,
+            &State::open_in_memory().unwrap(),
+            OnEdit::default(),
+            SyncType::Real,
+        )
+        .await
+        .unwrap();
+
+    assert!(servers
+        .spond_writes()
+        .await
+        .iter()
+        .all(|c| c.path != "/sponds/SPOND_D"));
+    assert_eq!(report.deleted, vec![]);
+    assert_eq!(
+        report
+            .reported
+            .iter()
+            .map(|d| (d.spond.id.clone(), d.removal.clone()))
+            .collect::<Vec<_>>(),
+        vec![(spond::SpondId::new("SPOND_D"), team_config::Removal::Report)]
+    );
+}
+
+#[tokio::test]
+async fn does_not_retry_cancels() {
+    let servers = start_servers().await;
+    Mock::given(method("POST"))
+        .and(path("/sponds/SPOND_D/cancel"))
+        .respond_with(ResponseTemplate::new(503))
+        .with_priority(1)
+        .mount(&servers.spond)
+        .await;
+    let mut team = team();
+    team.spond.removal = team_config::Removal::Cancel {
+        reason: "Removed from Full-Time".to_owned(),
+    };
+
+    let report = servers
+        .sync(
+            &team,
+            &State::open_in_memory().unwrap(),
+            OnEdit::default(),
+            SyncType::Real,
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(
+        servers
+            .spond_writes()
+            .await
+            .iter()
+            .filter(|c| c.path == "/sponds/SPOND_D/cancel")
+            .count(),
+        1
+    );
+    assert_eq!(report.errors.len(), 1);
+}
+
+/// State in which SPOND_B was last synced as `last_synced`.
+fn state_with_spond_b_synced(last_synced: serde_json::Value) -> State {
+    let fixture = full_time::Fixture {
+        id: Some(full_time::FixtureId::new("101")),
+        typ: full_time::FixtureType::League,
+        side: full_time::FixtureSide::Home,
+        date_time: kick_off_in(20, 9, 0),
+        opposition: READING.to_owned(),
+        venue: full_time::Venue::Known("WoodfordPark3G".to_owned()),
+    };
+    let state = State::open_in_memory().unwrap();
+    state
+        .record(
+            "Jedis",
+            &serde_json::from_value(last_synced.clone()).unwrap(),
+            &fixture,
+            &last_synced,
+        )
+        .unwrap();
+    state
+}
+
+/// State in which SPOND_B was last synced with a different meetup time, so it
+/// has since been edited in Spond.
+fn state_with_spond_b_edited() -> State {
+    let mut last_synced = spond_json("SPOND_B", Some("id:101"), READING, kick_off_in(20, 9, 0));
+    last_synced["meetupPrior"] = 30.into();
+    state_with_spond_b_synced(last_synced)
+}
+
+#[tokio::test]
+async fn skips_sponds_edited_since_the_last_sync() {
+    let servers = start_servers().await;
+
+    let report = servers
+        .sync(
+            &team(),
+            &state_with_spond_b_edited(),
+            OnEdit::Skip,
+            SyncType::Real,
+        )
+        .await
+        .unwrap();
+
+    assert!(servers
+        .spond_writes()
+        .await
+        .iter()
+        .all(|c| c.path != "/sponds/SPOND_B"));
+    assert_eq!(report.updated, vec![]);
+    assert_eq!(
+        report
+            .edited
+            .iter()
+            .map(|e| (e.spond.id.clone(), e.fields.clone(), e.overwritten))
+            .collect::<Vec<_>>(),
+        vec![(spond::SpondId::new("SPOND_B"), vec![Field::Meetup], false)]
+    );
+}
+
+#[tokio::test]
+async fn refuses_to_overwrite_edits_unless_forced() {
+    let servers = start_servers().await;
+
+    let refused = servers
+        .sync(
+            &team(),
+            &state_with_spond_b_edited(),
+            OnEdit::Refuse,
+            SyncType::Real,
+        )
+        .await;
+    assert!(matches!(refused, Err(Error::Edited(edited)) if edited.len() == 1));
+    assert_eq!(servers.spond_writes().await, vec![]);
+
+    let state = state_with_spond_b_edited();
+    servers
+        .sync(&team(), &state, OnEdit::Overwrite, SyncType::Real)
+        .await
+        .unwrap();
+    assert!(servers
+        .spond_writes()
+        .await
+        .iter()
+        .any(|c| c.path == "/sponds/SPOND_B"));
+    let record = &state.records("Jedis").unwrap()[&spond::SpondId::new("SPOND_B")];
+    assert_eq!(record.fixture.date_time, kick_off_in(21, 10, 0));
+    assert_eq!(record.request["meetupPrior"], 15);
+}
+
+#[tokio::test]
+async fn dry_run_makes_no_changes() {
+    let servers = start_servers().await;
+
+    let report = servers
+        .sync(
+            &team(),
+            &State::open_in_memory().unwrap(),
+            OnEdit::default(),
+            SyncType::Dry,
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(servers.spond_writes().await, vec![]);
+    assert_eq!(
+        report
+            .created
+            .iter()
+            .map(|f| f.opposition.as_str())
+            .collect::<Vec<_>>(),
+        vec![EMMBROOK]
+    );
+    assert_eq!(
+        report
+            .updated
+            .iter()
+            .map(|u| (u.spond.id.clone(), u.after.date_time))
+            .collect::<Vec<_>>(),
+        vec![(spond::SpondId::new("SPOND_B"), kick_off_in(21, 10, 0))]
+    );
+    assert_eq!(
+        report.updated[0].changes,
+        vec![FixtureChange::KickOff {
+            before: kick_off_in(20, 9, 0),
+            after: kick_off_in(21, 10, 0),
+        }]
+    );
+    assert_eq!(
+        report
+            .deleted
+            .iter()
+            .map(|d| d.spond.id.clone())
+            .collect::<Vec<_>>(),
+        vec![spond::SpondId::new("SPOND_D")]
+    );
+}
+
+#[tokio::test]
+async fn report_serialises_to_the_documented_schema() {
+    let servers = start_servers().await;
+
+    let report = servers
+        .sync(
+            &team(),
+            &State::open_in_memory().unwrap(),
+            OnEdit::default(),
+            SyncType::Dry,
+        )
+        .await
+        .unwrap();
+
+    let json = serde_json::to_value(&report).unwrap();
+    assert_eq!(json["team"], "Jedis");
+    assert_eq!(json["sync_type"], "dry");
+    assert_eq!(
+        json["created"][0],
+        serde_json::json!({
+            "id": "102",
+            "type": "league",
+            "side": "away",
+            "date_time": kick_off_in(28, 9, 30).to_rfc3339_opts(SecondsFormat::AutoSi, true),
+            "opposition": EMMBROOK,
+            "venue": {"other": {"name": "Emmbrook Sports Club", "address": ""}}
+        })
+    );
+    assert_eq!(json["updated"][0]["spond"]["id"], "SPOND_B");
+    assert_eq!(
+        json["updated"][0]["after"]["venue"]["known"],
+        "WoodfordPark3G"
+    );
+    assert_eq!(json["deleted"][0]["spond"]["id"], "SPOND_D");
+}